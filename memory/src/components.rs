@@ -2,6 +2,7 @@ use {
     bevy::prelude::*,
     duplicate::*,
     enum_dispatch::enum_dispatch,
+    serde::{Deserialize, Serialize},
     std::{
         fmt::{self, Display, Formatter},
         ops::Deref,
@@ -13,6 +14,7 @@ duplicate! {[
     [Open]          [Open];
     [Close]         [Close];
     [Revealed]      [Revealed];
+    [Animating]     [Card turning or shaking, the moves on its board wait till it settles];
 ]
     ///comment
     #[derive(Debug, Copy, Clone, Component)]
@@ -20,9 +22,9 @@ duplicate! {[
     pub struct component_type;
 }
 
-duplicate! {[component t; [Idx] [usize]; [Flesh] [u8]; [Bolts] [u8];]
+duplicate! {[component t; [Idx] [usize]; [Flesh] [u8]; [Bolts] [u8]; [Wire] [u8];]
 #[cfg_attr(feature = "debug", derive(bevy_inspector_egui::Inspectable))]
-#[derive(Component, Copy, Clone, Debug)]
+#[derive(Component, Copy, Clone, Debug, Serialize, Deserialize)]
 pub struct component(pub t, pub u8);
 impl Deref for component {
     type Target = t;
@@ -47,35 +49,162 @@ impl Idx {
 pub trait PlayerOps {
     fn inc_turn(&mut self);
 }
-#[duplicate_item(pl; [Flesh]; [Bolts];)]
+#[duplicate_item(pl; [Flesh]; [Bolts]; [Wire];)]
 impl PlayerOps for pl {
     fn inc_turn(&mut self) {
         self.1 += 1;
     }
 }
 #[enum_dispatch(PlayerOps, Deref)]
-#[derive(Debug, Component, Copy, Clone)]
+#[derive(Debug, Component, Copy, Clone, Serialize, Deserialize)]
 pub enum Player {
     Flesh(Flesh),
     Bolts(Bolts),
+    /// Human playing from another machine
+    Wire(Wire),
 }
 impl Player {
     pub fn deref(&self) -> (u8, u8) {
         match self {
             Player::Flesh(Flesh(x, y)) => (*x, *y),
             Player::Bolts(Bolts(x, y)) => (*x, *y),
+            Player::Wire(Wire(x, y)) => (*x, *y),
         }
     }
     pub fn id(&self) -> u8 {
         match self {
             Player::Flesh(Flesh(x, _)) => *x,
             Player::Bolts(Bolts(x, _)) => *x,
+            Player::Wire(Wire(x, _)) => *x,
         }
     }
     pub fn is_bot(&self) -> bool {
         match self {
-            Player::Flesh(_) => false,
+            Player::Flesh(_) | Player::Wire(_) => false,
             Player::Bolts(_) => true,
         }
     }
+    pub fn is_remote(&self) -> bool {
+        matches!(self, Player::Wire(_))
+    }
+    /// Same kind of player on another seat, yet to take a turn
+    pub fn seated(&self, seat: u8) -> Self {
+        match self {
+            Player::Flesh(_) => Player::Flesh(Flesh(seat, 0)),
+            Player::Bolts(_) => Player::Bolts(Bolts(seat, 0)),
+            Player::Wire(_) => Player::Wire(Wire(seat, 0)),
+        }
+    }
+    /// Team of the player, seats alternate between the teams
+    pub fn team(&self, teams: u8) -> u8 {
+        self.id() % teams.max(1)
+    }
+}
+/// Board the entity is part of, for the cards and the score panel
+#[derive(Debug, Component, Copy, Clone, PartialEq, Eq)]
+pub struct InBoard(pub Entity);
+/// Text showing the total score of a team
+#[derive(Debug, Component, Copy, Clone)]
+pub struct TeamScore(pub u8);
+pub fn team_name(team: u8) -> char {
+    (b'A' + team) as char
+}
+/// Direction to move the cursor in on the grid of cards
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Dir {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+/// Layout of the cards of a board, in two halves growing out from the middle of the screen.
+/// Must be used as a component on the `Board`
+#[derive(Debug, Component, Copy, Clone)]
+pub struct Grid {
+    /// Cards in a full row
+    pub width: usize,
+    /// Cards in each half
+    pub count: usize,
+}
+impl Grid {
+    pub fn new(count: usize) -> Self {
+        let width = (2. * count as f32).sqrt().round().max(1.) as usize;
+        Grid { width, count }
+    }
+    /// Rows in each half
+    pub fn rows(&self) -> usize {
+        (self.count + self.width - 1) / self.width
+    }
+    /// Half and its row of a row on screen, the rows of both halves count from the middle
+    fn row(&self, y: usize) -> Option<(usize, usize)> {
+        let rows = self.rows();
+        match y {
+            y if y < rows => Some((0, rows - 1 - y)),
+            y if y < 2 * rows => Some((1, y - rows)),
+            _ => None,
+        }
+    }
+    /// Cards in a row on screen, the outer rows may not be full
+    fn row_len(&self, y: usize) -> usize {
+        self.row(y)
+            .map_or(0, |(_, row)| self.width.min(self.count - row * self.width))
+    }
+    /// Column and row of the card on screen, from the top left
+    pub fn pos(&self, idx: usize) -> (usize, usize) {
+        let (half, idx) = (idx / self.count, idx % self.count);
+        let (x, row) = (idx % self.width, idx / self.width);
+        match half {
+            0 => (x, self.rows() - 1 - row),
+            _ => (x, self.rows() + row),
+        }
+    }
+    /// Card at the column and row on screen
+    pub fn at(&self, x: usize, y: usize) -> Option<usize> {
+        let (half, row) = self.row(y)?;
+        let idx = Idx::from2d(x, row, self.width).0;
+        (x < self.width && idx < self.count).then_some(half * self.count + idx)
+    }
+    /// Card next to the given one, wrapping around the edges.
+    /// Rows that are not full are centered, so moving across rows keeps to the closest card
+    pub fn step(&self, idx: usize, dir: Dir) -> usize {
+        let (x, y) = self.pos(idx);
+        let (len, rows) = (self.row_len(y), 2 * self.rows());
+        let wrap = |v: usize, by: isize, len: usize| (v as isize + by).rem_euclid(len as isize);
+        let to = match dir {
+            Dir::Left => return self.at(wrap(x, -1, len) as usize, y).unwrap_or(idx),
+            Dir::Right => return self.at(wrap(x, 1, len) as usize, y).unwrap_or(idx),
+            Dir::Up => wrap(y, -1, rows) as usize,
+            Dir::Down => wrap(y, 1, rows) as usize,
+        };
+        let center = x as f32 + (self.width - len) as f32 / 2.;
+        let to_len = self.row_len(to);
+        let x = (center - (self.width - to_len) as f32 / 2.).round();
+        self.at(x.clamp(0., to_len as f32 - 1.) as usize, to)
+            .unwrap_or(idx)
+    }
+}
+/// Card in focus on a board, to play with the keys. Must be used as a component on the `Board`
+#[derive(Debug, Component, Copy, Clone, Default)]
+pub struct Cursor {
+    pub idx: usize,
+    /// Whether the focus ring is shown, from the first use of the keys till a click
+    pub shown: bool,
+}
+/// Zoom of a board, pinched and panned with two fingers. Must be used as a component on the `Board`
+#[derive(Debug, Component, Copy, Clone)]
+pub struct Zoom {
+    pub scale: f32,
+    /// Offset of the board on screen, to keep the pinch under the fingers
+    pub pan: Vec2,
+}
+impl Default for Zoom {
+    fn default() -> Self {
+        Zoom {
+            scale: 1.,
+            pan: Vec2::ZERO,
+        }
+    }
 }
+/// Flips of a found pair, shown while it is long pressed
+#[derive(Debug, Component, Copy, Clone)]
+pub struct Peek;
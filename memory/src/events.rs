@@ -0,0 +1,140 @@
+//! Events of a game, sent as the moves are played on the `Deck` of a board
+use {
+    crate::{Deck, Outcome},
+    bevy::{ecs::system::SystemParam, prelude::*},
+    std::time::Duration,
+};
+
+/// Card turned face up by the player
+#[derive(Debug, Clone, Copy)]
+pub struct CardFlipped {
+    pub board: Entity,
+    pub idx: usize,
+    pub card: u8,
+    pub player: u8,
+}
+/// Card turned back face down, taken back by the player
+#[derive(Debug, Clone, Copy)]
+pub struct FlipUndone {
+    pub board: Entity,
+    pub idx: usize,
+    pub player: u8,
+}
+/// Pair found by the player
+#[derive(Debug, Clone, Copy)]
+pub struct PairMatched {
+    pub board: Entity,
+    pub cards: [usize; 2],
+    pub player: u8,
+}
+/// Cards flipped by the player that are not a pair
+#[derive(Debug, Clone, Copy)]
+pub struct Mismatch {
+    pub board: Entity,
+    pub cards: [usize; 2],
+    pub player: u8,
+}
+/// Turn passed from one player to another, or back to the same one after a card taken back
+#[derive(Debug, Clone, Copy)]
+pub struct TurnChanged {
+    pub board: Entity,
+    pub from: u8,
+    pub to: u8,
+}
+/// Last pair of the board is found
+#[derive(Debug, Clone)]
+pub struct GameFinished {
+    pub board: Entity,
+    pub outcome: Outcome,
+}
+
+/// Events of the moves, also to play them without the `MemoryGamePlugin`
+pub fn add_events(app: &mut App) {
+    app.add_event::<CardFlipped>()
+        .add_event::<FlipUndone>()
+        .add_event::<PairMatched>()
+        .add_event::<Mismatch>()
+        .add_event::<TurnChanged>()
+        .add_event::<GameFinished>();
+}
+
+/// Play moves on the `Deck`, sending the events of each
+#[derive(SystemParam)]
+pub struct Moves<'w, 's> {
+    flipped: EventWriter<'w, 's, CardFlipped>,
+    undone: EventWriter<'w, 's, FlipUndone>,
+    matched: EventWriter<'w, 's, PairMatched>,
+    mismatch: EventWriter<'w, 's, Mismatch>,
+    turn: EventWriter<'w, 's, TurnChanged>,
+    finished: EventWriter<'w, 's, GameFinished>,
+}
+impl<'w, 's> Moves<'w, 's> {
+    pub fn play(&mut self, board: Entity, deck: &mut Deck, idx: usize) {
+        let player = deck.player();
+        deck.play(idx);
+        self.flipped.send(CardFlipped {
+            board,
+            idx,
+            card: deck.get_card(idx),
+            player,
+        });
+        if deck.opened.len() == 2 {
+            let cards = [deck.opened[0], deck.opened[1]];
+            if deck.is_revealed(idx) {
+                self.matched.send(PairMatched {
+                    board,
+                    cards,
+                    player,
+                });
+            } else {
+                self.mismatch.send(Mismatch {
+                    board,
+                    cards,
+                    player,
+                });
+            }
+        }
+        if let Some(outcome) = deck.outcome() {
+            self.finished.send(GameFinished {
+                board,
+                outcome: outcome.clone(),
+            });
+        } else if deck.player() != player {
+            self.turn.send(TurnChanged {
+                board,
+                from: player,
+                to: deck.player(),
+            });
+        }
+    }
+    /// Take back the first card of the turn on the `Deck`, if it can be, ending the turn
+    pub fn undo(&mut self, board: Entity, deck: &mut Deck) -> Option<usize> {
+        let player = deck.player();
+        let idx = deck.undo()?;
+        self.undone.send(FlipUndone { board, idx, player });
+        if let Some(outcome) = deck.outcome() {
+            self.finished.send(GameFinished {
+                board,
+                outcome: outcome.clone(),
+            });
+        } else {
+            self.turn.send(TurnChanged {
+                board,
+                from: player,
+                to: deck.player(),
+            });
+        }
+        Some(idx)
+    }
+    /// Run the clock of the `Deck`, sending the end of a game that ran out of time
+    pub fn tick(&mut self, board: Entity, deck: &mut Deck, delta: Duration) {
+        let completed = deck.completed();
+        deck.tick(delta);
+        if let Some(outcome) = deck.outcome().filter(|_| !completed) {
+            self.finished.send(GameFinished {
+                board,
+                outcome: outcome.clone(),
+            });
+        }
+    }
+}
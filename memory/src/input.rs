@@ -0,0 +1,553 @@
+//! Actions of the players, mapped from the mouse, touch, keyboard and gamepads.
+//!
+//! Keys and gamepad buttons go through the `Bindings` of the options, while a click or tap flips
+//! the card under it and the left stick always moves the cursor.
+use {
+    crate::{components::*, net::NetClient, Board, Deck, MemoryGAssts, MemoryGOpts, Pads},
+    bevy::prelude::*,
+    serde::{Deserialize, Serialize},
+    std::collections::HashMap,
+};
+
+/// Longest press to take as a tap, in seconds
+const TAP: f32 = 0.3;
+/// Shortest press to peek at a found pair, in seconds
+const LONG_PRESS: f32 = 0.5;
+/// Farthest a finger may move for a tap or a long press, in pixels
+const SLOP: f32 = 12.;
+
+/// What a player does, whatever the input it came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    FlipCard(usize),
+    MoveCursor(Dir),
+    /// Take back the first card of the turn
+    Undo,
+    Pause,
+    NewGame,
+}
+/// Action of a player on a board
+#[derive(Debug, Clone, Copy)]
+pub struct PlayerAction {
+    /// Board acted on, any of them for the actions of the whole game
+    pub board: Entity,
+    /// Seat of the gamepad, or the player on their turn for the other inputs
+    pub seat: u8,
+    pub action: Action,
+}
+
+/// Inputs to bind the keys and gamepad buttons to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Control {
+    Up,
+    Down,
+    Left,
+    Right,
+    Flip,
+    Undo,
+    Pause,
+    NewGame,
+}
+impl Control {
+    pub const ALL: [Control; 8] = [
+        Control::Up,
+        Control::Down,
+        Control::Left,
+        Control::Right,
+        Control::Flip,
+        Control::Undo,
+        Control::Pause,
+        Control::NewGame,
+    ];
+    /// Action of the control, where the card in focus is flipped
+    pub fn action(&self, focus: usize) -> Action {
+        match self {
+            Control::Up => Action::MoveCursor(Dir::Up),
+            Control::Down => Action::MoveCursor(Dir::Down),
+            Control::Left => Action::MoveCursor(Dir::Left),
+            Control::Right => Action::MoveCursor(Dir::Right),
+            Control::Flip => Action::FlipCard(focus),
+            Control::Undo => Action::Undo,
+            Control::Pause => Action::Pause,
+            Control::NewGame => Action::NewGame,
+        }
+    }
+    /// Whether the control plays on the cursor, only showing it when it is hidden
+    fn on_cursor(&self) -> bool {
+        !matches!(self, Control::Undo | Control::Pause | Control::NewGame)
+    }
+}
+
+/// Keys and gamepad buttons bound to the controls, a control may have several of each
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Bindings {
+    pub keys: Vec<(Control, KeyCode)>,
+    pub buttons: Vec<(Control, GamepadButtonType)>,
+}
+impl Default for Bindings {
+    fn default() -> Self {
+        use {Control::*, GamepadButtonType as Button};
+        Self {
+            keys: vec![
+                (Up, KeyCode::Up),
+                (Up, KeyCode::W),
+                (Down, KeyCode::Down),
+                (Down, KeyCode::S),
+                (Left, KeyCode::Left),
+                (Left, KeyCode::A),
+                (Right, KeyCode::Right),
+                (Right, KeyCode::D),
+                (Flip, KeyCode::Return),
+                (Flip, KeyCode::NumpadEnter),
+                (Flip, KeyCode::Space),
+                (Undo, KeyCode::U),
+                (Undo, KeyCode::Back),
+                (Pause, KeyCode::Escape),
+                (NewGame, KeyCode::N),
+            ],
+            buttons: vec![
+                (Up, Button::DPadUp),
+                (Down, Button::DPadDown),
+                (Left, Button::DPadLeft),
+                (Right, Button::DPadRight),
+                (Flip, Button::South),
+                (Undo, Button::West),
+                (Pause, Button::Start),
+            ],
+        }
+    }
+}
+impl Bindings {
+    /// Controls of the keys just pressed
+    pub fn keys(&self, input: &Input<KeyCode>) -> Vec<Control> {
+        let mut controls: Vec<Control> = self
+            .keys
+            .iter()
+            .filter(|(_, key)| input.just_pressed(*key))
+            .map(|(control, _)| *control)
+            .collect();
+        controls.dedup();
+        controls
+    }
+    /// Controls of the buttons just pressed on the gamepad
+    pub fn buttons(&self, input: &Input<GamepadButton>, gamepad: Gamepad) -> Vec<Control> {
+        let mut controls: Vec<Control> = self
+            .buttons
+            .iter()
+            .filter(|(_, button)| input.just_pressed(GamepadButton::new(gamepad, *button)))
+            .map(|(control, _)| *control)
+            .collect();
+        controls.dedup();
+        controls
+    }
+    /// Bind the key to the control, in place of its other keys
+    pub fn bind_key(&mut self, control: Control, key: KeyCode) {
+        self.keys.retain(|&(c, k)| c != control && k != key);
+        self.keys.push((control, key));
+    }
+    /// Bind the gamepad button to the control, in place of its other buttons
+    pub fn bind_button(&mut self, control: Control, button: GamepadButtonType) {
+        self.buttons.retain(|&(c, b)| c != control && b != button);
+        self.buttons.push((control, button));
+    }
+    /// Keys and buttons of the control
+    pub fn desc(&self, control: Control) -> String {
+        let keys: Vec<String> = self
+            .keys
+            .iter()
+            .filter(|(c, _)| *c == control)
+            .map(|(_, key)| format!("{key:?}"))
+            .collect();
+        let buttons: Vec<String> = self
+            .buttons
+            .iter()
+            .filter(|(c, _)| *c == control)
+            .map(|(_, button)| format!("{button:?}"))
+            .collect();
+        format!("{} | {}", keys.join(", "), buttons.join(", "))
+    }
+}
+
+/// Whether the player on their turn plays on this machine
+fn is_local(deck: &Deck, client: Option<&NetClient>) -> bool {
+    let player = deck.seats()[deck.player() as usize];
+    match client {
+        Some(client) => client.seat == player.id(),
+        None => !player.is_bot() && !player.is_remote(),
+    }
+}
+
+/// Map the keys to the actions of a local human on their turn, the first one of them in a race
+pub fn keyboard(
+    keys: Res<Input<KeyCode>>,
+    opts: Res<MemoryGOpts>,
+    mut boards: Query<(Entity, &Deck, &mut Cursor)>,
+    client: Option<Res<NetClient>>,
+    mut actions: EventWriter<PlayerAction>,
+) {
+    let controls = opts.bindings.keys(&keys);
+    if controls.is_empty() {
+        return;
+    }
+    let local = boards
+        .iter()
+        .filter(|(_, deck, _)| !deck.completed() && is_local(deck, client.as_deref()))
+        .min_by_key(|(_, deck, _)| deck.player())
+        .map(|(board, ..)| board);
+    for control in controls {
+        let Some((board, deck, mut cursor)) = (match local {
+            Some(board) => boards.get_mut(board).ok(),
+            None if !control.on_cursor() => boards.iter_mut().next(),
+            None => None,
+        }) else {
+            continue;
+        };
+        // The first key only shows where the cursor is
+        if control.on_cursor() && !cursor.shown {
+            cursor.shown = true;
+            continue;
+        }
+        actions.send(PlayerAction {
+            board,
+            seat: deck.player(),
+            action: control.action(cursor.idx),
+        });
+    }
+}
+/// Map the buttons and the left stick of each gamepad to the actions of its seat.
+/// The players waiting for their turn move a cursor of their own
+#[allow(clippy::too_many_arguments)]
+pub fn gamepad(
+    gamepads: Res<Gamepads>,
+    buttons: Res<Input<GamepadButton>>,
+    axes: Res<Axis<GamepadAxis>>,
+    opts: Res<MemoryGOpts>,
+    mut pads: ResMut<Pads>,
+    mut boards: Query<(Entity, &Deck, &mut Cursor)>,
+    client: Option<Res<NetClient>>,
+    mut actions: EventWriter<PlayerAction>,
+) {
+    pads.sync(&gamepads);
+    for (nth, pad) in pads.iter_mut().enumerate() {
+        let Some(pad) = pad else { continue };
+        let mut controls = opts.bindings.buttons(&buttons, pad.gamepad);
+        let axis = |axis| axes.get(GamepadAxis::new(pad.gamepad, axis)).unwrap_or(0.);
+        let (x, y) = (axis(GamepadAxisType::LeftStickX), axis(GamepadAxisType::LeftStickY));
+        let stick = if x.abs().max(y.abs()) < 0.5 {
+            None
+        } else if x.abs() > y.abs() {
+            Some(if x > 0. { Control::Right } else { Control::Left })
+        } else {
+            Some(if y > 0. { Control::Up } else { Control::Down })
+        };
+        // A push of the stick moves only once
+        controls.extend(stick.filter(|_| stick != pad.stick));
+        pad.stick = stick;
+        if controls.is_empty() {
+            continue;
+        }
+
+        for (board, deck, mut cursor) in boards.iter_mut() {
+            let local = client.as_ref().map(|client| client.seat);
+            let Some(seat) = Pads::seat(deck, nth, local) else { continue };
+            if deck.completed() || deck.mode().race && deck.player() != seat {
+                continue;
+            }
+            let on_turn = deck.player() == seat;
+            for control in controls.iter() {
+                // The first push only shows where the cursor is
+                if on_turn && control.on_cursor() && !cursor.shown {
+                    cursor.shown = true;
+                    continue;
+                }
+                actions.send(PlayerAction {
+                    board,
+                    seat,
+                    action: control.action(if on_turn { cursor.idx } else { pad.idx }),
+                });
+            }
+        }
+    }
+}
+/// Map the clicks on the cards to flips by the player on their turn.
+/// The cursor follows them, with its ring hidden till the keys are used
+#[allow(clippy::type_complexity)]
+pub fn pointer(
+    cards: Query<(&Idx, &InBoard, &Interaction), Changed<Interaction>>,
+    mut boards: Query<(&Deck, &mut Cursor)>,
+    touches: Res<Touches>,
+    mut actions: EventWriter<PlayerAction>,
+) {
+    // Clicks emulated from the fingers are left to the taps
+    if touches.iter().next().is_some() {
+        return;
+    }
+    for (id, board, _) in cards.iter().filter(|x| *x.2 == Interaction::Clicked) {
+        let Ok((deck, mut cursor)) = boards.get_mut(board.0) else { continue };
+        if deck.completed() || !deck.is_available_move(id.0) {
+            continue;
+        }
+        *cursor = Cursor {
+            idx: id.0,
+            ..default()
+        };
+        actions.send(PlayerAction {
+            board: board.0,
+            seat: deck.player(),
+            action: Action::FlipCard(id.0),
+        });
+    }
+}
+
+/// Whether the point on screen is within the node, as scaled by the zoom
+fn hit(node: &Node, global: &GlobalTransform, at: Vec2) -> bool {
+    let transform = global.compute_transform();
+    let extents = node.size() * transform.scale.truncate() / 2.;
+    (at - transform.translation.truncate()).abs().cmple(extents).all()
+}
+/// Map the taps on the cards to flips by the player on their turn, and the other gestures.
+/// A long press peeks at the flips of a found pair, and two fingers pinch and pan the board.
+/// A finger down along with another one never flips, so a turn takes one card per tap
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub fn touch(
+    mut cmd: Commands,
+    touches: Res<Touches>,
+    time: Res<Time>,
+    // Start of each press, and whether another finger was down along with it
+    mut presses: Local<HashMap<u64, (f32, bool)>>,
+    // Distance between the two fingers pinching and their middle
+    mut pinch: Local<Option<(f32, Vec2)>>,
+    mut boards: Query<
+        (&Deck, &mut Cursor, &mut Zoom, &mut Transform, &mut Style, &Node, &GlobalTransform),
+        With<Board>,
+    >,
+    cards: Query<(&Idx, &InBoard, &Node, &GlobalTransform)>,
+    peeks: Query<Entity, With<Peek>>,
+    assets: Res<MemoryGAssts>,
+    mut actions: EventWriter<PlayerAction>,
+) {
+    let now = time.elapsed_seconds();
+    let fingers: Vec<&Touch> = touches.iter().collect();
+    for touch in touches.iter_just_pressed() {
+        presses.insert(touch.id(), (now, false));
+    }
+    if fingers.len() > 1 {
+        presses.values_mut().for_each(|press| press.1 = true);
+    }
+    let card_at = |at| {
+        cards
+            .iter()
+            .find(|(.., node, global)| hit(node, global, at))
+            .map(|(id, board, ..)| (board.0, **id, id.1))
+    };
+
+    let mut flipped = vec![];
+    for touch in touches.iter_just_released() {
+        let Some((since, multi)) = presses.remove(&touch.id()) else { continue };
+        if multi || now - since > TAP || touch.distance().length() > SLOP {
+            continue;
+        }
+        let Some((board, idx, _)) = card_at(touch.position()) else { continue };
+        let Ok((deck, mut cursor, ..)) = boards.get_mut(board) else { continue };
+        if flipped.contains(&board) || deck.completed() || !deck.is_available_move(idx) {
+            continue;
+        }
+        flipped.push(board);
+        *cursor = Cursor {
+            idx,
+            ..default()
+        };
+        actions.send(PlayerAction {
+            board,
+            seat: deck.player(),
+            action: Action::FlipCard(idx),
+        });
+    }
+    // Presses cancelled, or released while the game was not in play
+    presses.retain(|&id, _| touches.get_pressed(id).is_some());
+
+    let peek = fingers
+        .iter()
+        .filter(|touch| touch.distance().length() <= SLOP)
+        .filter(|touch| {
+            presses
+                .get(&touch.id())
+                .map_or(false, |&(since, multi)| !multi && now - since >= LONG_PRESS)
+        })
+        .find_map(|touch| {
+            let (board, idx, flips) = card_at(touch.position())?;
+            let revealed = boards.get(board).map_or(false, |b| b.0.is_revealed(idx));
+            revealed.then_some((touch.position(), flips))
+        });
+    match peek {
+        Some((at, flips)) if peeks.is_empty() => {
+            cmd.spawn(TextBundle {
+                text: Text::from_section(
+                    format!("Flipped {flips} times"),
+                    TextStyle {
+                        color: assets.count_color(flips),
+                        font: assets.score_font.clone(),
+                        font_size: 27.,
+                    },
+                ),
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        left: Val::Px(at.x),
+                        top: Val::Px(at.y - 2. * SLOP - 27.),
+                        ..default()
+                    },
+                    ..default()
+                },
+                ..default()
+            })
+            .insert(Peek);
+        }
+        None => peeks.for_each(|peek| cmd.entity(peek).despawn_recursive()),
+        _ => (),
+    }
+
+    let [one, other] = fingers[..] else {
+        *pinch = None;
+        return;
+    };
+    let spread = one.position().distance(other.position());
+    let middle = (one.position() + other.position()) / 2.;
+    if let Some((last_spread, last_middle)) = pinch.replace((spread, middle)) {
+        let Some((.., mut zoom, mut transform, mut style, _, global)) = boards
+            .iter_mut()
+            .find(|(.., node, global)| hit(node, global, middle))
+        else {
+            return;
+        };
+        let scale = (zoom.scale * spread / last_spread.max(1.)).clamp(1., 4.);
+        // Scaled around the middle of the board, so moved to keep the pinch under the fingers
+        let center = global.translation().truncate();
+        zoom.pan += (middle - center) * (1. - scale / zoom.scale) + middle - last_middle;
+        zoom.scale = scale;
+        if scale <= 1. {
+            zoom.pan = Vec2::ZERO;
+        }
+        transform.scale = Vec3::new(scale, scale, 1.);
+        style.margin = UiRect {
+            left: Val::Px(zoom.pan.x),
+            top: Val::Px(zoom.pan.y),
+            ..default()
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{MemoryStorage, Mode, Store},
+        bevy::input::{
+            gamepad::{GamepadEventRaw, GamepadEventType},
+            touch::{TouchInput, TouchPhase},
+            InputPlugin,
+        },
+    };
+
+    fn send(app: &mut App, id: usize, event: GamepadEventType) {
+        app.world
+            .resource_mut::<Events<GamepadEventRaw>>()
+            .send(GamepadEventRaw::new(Gamepad::new(id), event));
+    }
+    fn actions(app: &mut App) -> Vec<(u8, Action)> {
+        app.world
+            .resource_mut::<Events<PlayerAction>>()
+            .drain()
+            .map(|action| (action.seat, action.action))
+            .collect()
+    }
+    fn deck() -> Deck {
+        let seats: Vec<Player> = (0..2).map(|s| Flesh(s, 0).into()).collect();
+        Deck::masked(Mode::default(), &[0, 14, 1, 15], &seats)
+    }
+    fn finger(app: &mut App, id: u64, phase: TouchPhase, position: Vec2) {
+        app.world.resource_mut::<Events<TouchInput>>().send(TouchInput {
+            phase,
+            position,
+            force: None,
+            id,
+        });
+        app.update();
+    }
+
+    #[test]
+    fn gamepads_play_for_their_seats() {
+        let mut app = App::new();
+        app.add_plugin(InputPlugin)
+            .init_resource::<MemoryGOpts>()
+            .init_resource::<Pads>()
+            .add_event::<PlayerAction>()
+            .add_system(gamepad);
+        let cursor = Cursor {
+            idx: 2,
+            shown: true,
+        };
+        app.world.spawn((deck(), cursor));
+        send(&mut app, 0, GamepadEventType::Connected);
+        send(&mut app, 1, GamepadEventType::Connected);
+        app.update();
+        assert_eq!(app.world.resource::<Pads>().iter().flatten().count(), 2);
+
+        send(&mut app, 0, GamepadEventType::ButtonChanged(GamepadButtonType::South, 1.));
+        send(&mut app, 1, GamepadEventType::AxisChanged(GamepadAxisType::LeftStickX, 1.));
+        app.update();
+        assert_eq!(
+            actions(&mut app),
+            [(0, Action::FlipCard(2)), (1, Action::MoveCursor(Dir::Right))]
+        );
+
+        // Held buttons and sticks act only once
+        app.update();
+        assert_eq!(actions(&mut app), []);
+        send(&mut app, 1, GamepadEventType::ButtonChanged(GamepadButtonType::Start, 1.));
+        app.update();
+        assert_eq!(actions(&mut app), [(1, Action::Pause)]);
+    }
+
+    #[test]
+    fn taps_flip_the_card_under_them() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(AssetPlugin::default())
+            .add_plugin(InputPlugin)
+            .add_asset::<Image>()
+            .insert_resource(Store(Box::new(MemoryStorage::default())))
+            .init_resource::<MemoryGAssts>()
+            .add_event::<PlayerAction>()
+            .add_system(touch);
+        let board = app
+            .world
+            .spawn((deck(), Cursor::default(), Zoom::default(), Board))
+            .insert((Transform::default(), Style::default()))
+            .insert((Node::default(), GlobalTransform::default()))
+            .id();
+        // Cards with no size are hit right on their middle
+        let at = |idx: usize| Vec2::new(40. * idx as f32 + 20., 40.);
+        for idx in 0..4 {
+            let middle = GlobalTransform::from_translation(at(idx).extend(0.));
+            app.world.spawn((Idx(idx, 0), InBoard(board), Node::default(), middle));
+        }
+
+        finger(&mut app, 0, TouchPhase::Started, at(1));
+        finger(&mut app, 0, TouchPhase::Ended, at(1));
+        assert_eq!(actions(&mut app), [(0, Action::FlipCard(1))]);
+
+        // A finger down along with another one never flips
+        finger(&mut app, 1, TouchPhase::Started, at(2));
+        finger(&mut app, 2, TouchPhase::Started, at(3));
+        finger(&mut app, 1, TouchPhase::Ended, at(2));
+        finger(&mut app, 2, TouchPhase::Ended, at(3));
+        assert_eq!(actions(&mut app), []);
+
+        // Nor does a finger sliding away
+        finger(&mut app, 3, TouchPhase::Started, at(2));
+        finger(&mut app, 3, TouchPhase::Moved, at(3));
+        finger(&mut app, 3, TouchPhase::Ended, at(3));
+        assert_eq!(actions(&mut app), []);
+    }
+}
@@ -16,7 +16,11 @@ pub use {components::Player, resources::*};
 use {bevy::log, bevy_inspector_egui::InspectorPlugin};
 
 pub mod components;
+pub mod events;
+pub mod input;
 mod menu;
+pub mod net;
+pub mod spectate;
 mod resources;
 mod systems;
 pub mod tween;
@@ -42,6 +46,14 @@ pub struct ScoreBoard;
 pub struct MemoryGamePlugin<T>(pub T);
 impl<T: StateData + Copy> Plugin for MemoryGamePlugin<T> {
     fn build(&self, app: &mut App) {
+        events::add_events(app);
+        app.add_event::<input::PlayerAction>();
+        let store = app.world.get_resource_or_insert_with(Store::default);
+        let opts: Option<MemoryGOpts> = store.load("options.ron");
+        // Options are restored from the last run, unless set before the plugin
+        if !app.world.contains_resource::<MemoryGOpts>() {
+            app.insert_resource(opts.unwrap_or_default());
+        }
         app.add_state(GameState::Splash)
             .add_plugin(TweeningPlugin)
             .add_plugin(MenuPlugin)
@@ -50,25 +62,55 @@ impl<T: StateData + Copy> Plugin for MemoryGamePlugin<T> {
             .add_system_set(SystemSet::on_enter(Game).with_system(create_board))
             .add_system_set(
                 SystemSet::on_update(Game)
-                    .with_run_criteria(resource_exists::<Deck>)
+                    .with_run_criteria(deck_exists)
                     .with_system(systems::deck_complete.at_end())
+                    .with_system(input::keyboard.before(systems::turn))
+                    .with_system(input::gamepad.before(systems::turn))
+                    .with_system(input::pointer.before(systems::turn))
+                    .with_system(input::touch.before(systems::turn))
+                    .with_system(systems::cursor.after(systems::turn))
+                    .with_system(systems::shortcuts.after(systems::turn))
                     .with_system(systems::turn)
-                    .with_system(systems::score_board),
-            )
-            .add_system_set(
-                SystemSet::on_in_stack_update(Game)
-                    .with_run_criteria(resource_exists::<Deck>)
-                    .with_system(systems::uncover),
+                    .with_system(systems::clock)
+                    .with_system(
+                        net::client_turn
+                            .after(input::keyboard)
+                            .after(input::gamepad)
+                            .after(input::pointer)
+                            .after(input::touch),
+                    )
+                    .with_system(
+                        systems::score_board
+                            .after(systems::turn)
+                            .after(net::client_turn),
+                    )
+                    .with_system(
+                        systems::focus_ring
+                            .after(systems::cursor)
+                            .after(net::client_turn),
+                    ),
             )
+            .add_system_set(SystemSet::on_in_stack_update(Game).with_system(systems::uncover))
+            .add_system(spectate::stream)
+            .init_resource::<Themes>()
+            .init_resource::<CardPacks>()
+            .init_resource::<PackFaces>()
             .init_resource::<MemoryGAssts>()
+            .add_system(net::client_recv.with_run_criteria(resource_exists::<net::NetClient>))
             .add_system(board_display)
             .add_system(systems::card_flip)
+            .add_system(systems::retheme)
+            .add_system(systems::slice_atlas)
+            .add_system(systems::settle)
             .add_system_set(SystemSet::on_exit(Game).with_system(despawn::<Board>))
             .add_system_set(SystemSet::on_exit(Game).with_system(despawn::<ScoreBoard>))
             .add_system_set(SystemSet::on_enter(**self).with_system(splash_off))
             .add_system_set(SystemSet::on_in_stack_update(**self).with_system(on_completion))
             .add_system_set(SystemSet::on_exit(**self).with_system(splash_on))
-            .init_resource::<MemoryGOpts>();
+            .init_resource::<Profiles>()
+            .init_resource::<Series>()
+            .init_resource::<Ratings>()
+            .init_resource::<Pads>();
 
         #[cfg(feature = "dev")]
         app.add_plugin(InspectorPlugin::<MemoryGOpts>::new());
@@ -78,9 +120,21 @@ impl<T: StateData + Copy> Plugin for MemoryGamePlugin<T> {
 pub fn splash_on(mut state: ResMut<State<GameState>>) {
     state.push(GameState::Splash).unwrap();
 }
-pub fn splash_off(mut state: ResMut<State<GameState>>) {
+/// Start the first game right after the splash, without the menu. Must be used as a resource
+#[derive(Resource)]
+pub struct SkipMenu;
+pub fn splash_off(
+    mut cmd: Commands,
+    mut state: ResMut<State<GameState>>,
+    skip: Option<Res<SkipMenu>>,
+) {
     if state.inactives().is_empty() {
-        state.replace(GameState::Menu).unwrap();
+        if skip.is_some() {
+            cmd.remove_resource::<SkipMenu>();
+            state.replace(GameState::Game).unwrap();
+        } else {
+            state.replace(GameState::Menu).unwrap();
+        }
     } else {
         state.pop().unwrap();
     }
@@ -89,6 +143,10 @@ pub fn splash_off(mut state: ResMut<State<GameState>>) {
 pub fn resource_exists<T: Resource>(res: Option<Res<T>>) -> ShouldRun {
     res.is_some().into()
 }
+/// Run while a board has a game in play
+pub fn deck_exists(decks: Query<(), With<Deck>>) -> ShouldRun {
+    (!decks.is_empty()).into()
+}
 
 #[allow(clippy::type_complexity)]
 pub fn board_display(
@@ -102,50 +160,155 @@ pub fn board_display(
         }
     })
 }
-/// System to generate the complete board
-#[autodefault(except(Board, TransformScaleLens, Size, Text, TextAlignment))]
+/// System to generate the complete board, or a board for each racer in a race
 pub fn create_board(
     mut cmd: Commands,
     material: Res<MenuMaterials>,
     mut opts: ResMut<MemoryGOpts>,
     mut assets: ResMut<MemoryGAssts>,
+    profiles: Res<Profiles>,
+    mut series: ResMut<Series>,
+    asset_server: Res<AssetServer>,
+    packs: Res<CardPacks>,
+    mut faces: ResMut<PackFaces>,
+    mut images: ResMut<Assets<Image>>,
+    mut host: Option<ResMut<net::NetHost>>,
+    mut client: Option<ResMut<net::NetClient>>,
+    mut state: ResMut<State<GameState>>,
 ) {
     let mut rng = rand::thread_rng();
     assets.card.shuffle(&mut rng);
-    let count = opts.deck_params().0;
+    let pack = opts.pack.as_ref().and_then(|name| packs.get(name));
+    // Faces of a pack keyed by rank would hide the colors and suits the other rules pair by
+    let pack = pack.filter(|pack| {
+        let tells = pack.tells(opts.mode.rule);
+        if !tells {
+            warn!("The {} pack only tells the ranks, dealing the card font", pack.name);
+        }
+        tells
+    });
+    faces.load(pack, &asset_server, &mut images);
     opts.outcome = None;
-    let deck_width = (2. * count as f32).sqrt().round();
-    let players = opts.create_players();
-    let deck = Deck::init(opts.deck_params(), opts.mode, players.len() as u8);
-    let size = material.size / deck_width.max(2. * (count as f32 / deck_width).ceil()) * 0.77;
+    if opts.mode.race && client.is_none() {
+        // Remote humans can not see the other boards of a race, the menu tells the host why
+        if host.as_ref().map_or(false, |host| host.remotes() > 0) {
+            warn!("A race is only for the players of this machine");
+            state.overwrite_replace(Menu).unwrap();
+            return;
+        }
+        let players = opts.create_players(0);
+        let seats = opts.seats(&players, &profiles);
+        let seed = opts.seed.take().unwrap_or_else(rand::random);
+        let mode = Mode { teams: 0, ..opts.mode };
+        for pl in players.iter() {
+            let deck = Deck::racing(opts.deck_params(), mode, &players, pl.id(), seed);
+            let lane = (pl.id() as usize, players.len());
+            let board = spawn_board(
+                &mut cmd,
+                &material,
+                &opts,
+                &assets,
+                &faces,
+                &asset_server,
+                &deck,
+                &seats,
+                lane,
+            );
+            cmd.entity(board).insert(deck).insert(seats.clone());
+        }
+        return;
+    }
+    let (deck, players, seats) = match client.as_mut().and_then(|client| client.take_deal()) {
+        Some((deck, players)) => {
+            let seats = opts.seats(&players, &profiles);
+            (deck, players, seats)
+        }
+        None => {
+            let seed = opts.seed.take();
+            let (players, seats, seed, first) = series.deal(opts.best_of, seed, || {
+                let players = opts.create_players(host.as_ref().map_or(0, |host| host.remotes()));
+                let seats = opts.seats(&players, &profiles);
+                (players, seats)
+            });
+            let deck = Deck::seeded(opts.deck_params(), opts.mode, &players, seed);
+            (deck.starting_with(first), players, seats)
+        }
+    };
+    let board = spawn_board(
+        &mut cmd,
+        &material,
+        &opts,
+        &assets,
+        &faces,
+        &asset_server,
+        &deck,
+        &seats,
+        (0, 1),
+    );
+    if client.is_none() {
+        if let Some(host) = host.as_mut() {
+            host.deal(board, &deck, &players, opts.level);
+        }
+    }
+    cmd.entity(board).insert(deck).insert(seats);
+}
+/// Spawn the cards of the deck and the score panel, in a lane of the screen
+#[allow(clippy::too_many_arguments)]
+#[autodefault(except(Board, TransformScaleLens, Size, Text, TextAlignment))]
+fn spawn_board(
+    cmd: &mut Commands,
+    material: &MenuMaterials,
+    opts: &MemoryGOpts,
+    assets: &MemoryGAssts,
+    faces: &PackFaces,
+    asset_server: &AssetServer,
+    deck: &Deck,
+    seats: &Seats,
+    (lane, lanes): (usize, usize),
+) -> Entity {
+    let count = opts.deck_params().0;
+    let grid = Grid::new(count as usize);
+    let deck_width = grid.width as f32;
+    let size = material.size / deck_width.max(2. * (count as f32 / deck_width).ceil()) * 0.77
+        / lanes as f32;
 
     #[cfg(feature = "dev")]
     log::info!("{deck}\nsize {size}\ndeck_width {deck_width}");
 
     let width = (deck_width + 0.3) * (size + 2.);
     let seq = |i| {
-        Delay::new(Duration::from_millis(27 + i as u64 * 81)).then(Tween::new(
+        let motion = opts.motion;
+        let delay = Duration::from_millis(27 + i as u64 * 81);
+        Delay::new(motion.moves(delay)).then(Tween::new(
             EaseFunction::BounceOut,
-            Duration::from_millis(243),
+            motion.moves(Duration::from_millis(243)),
             TransformScaleLens {
                 start: Vec3::splat(0.27),
                 end: Vec3::ONE,
             },
         ))
     };
-    cmd.spawn(assets.back_ground.node(Style {
-        position_type: PositionType::Absolute,
-        size: Size::new(Val::Percent(100.), Val::Percent(100.)),
-        flex_direction: FlexDirection::Column,
-        justify_content: JustifyContent::Center,
-        align_items: AlignItems::Center,
-        align_self: AlignSelf::Center,
-    }))
-    .insert(FocusPolicy::Pass)
-    .insert(Name::new("Board"))
-    .insert(Board)
-    .with_children(|p| {
-        let mut card_iter = deck.iter().enumerate();
+    let board = cmd
+        .spawn(assets.back_ground.node(Style {
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                left: Val::Percent(100. * lane as f32 / lanes as f32),
+            },
+            size: Size::new(Val::Percent(100. / lanes as f32), Val::Percent(100.)),
+            flex_direction: FlexDirection::Column,
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            align_self: AlignSelf::Center,
+        }))
+        .insert(FocusPolicy::Pass)
+        .insert(Name::new("Board"))
+        .insert(Board)
+        .insert(grid)
+        .insert(Cursor::default())
+        .insert(Zoom::default())
+        .id();
+    cmd.entity(board).with_children(|p| {
+        let mut card_iter = 0..deck.len();
         for half in 0..2u8 {
             p.spawn(assets.back_ground.node(Style {
                 flex_basis: Val::Px(0.),
@@ -174,7 +337,8 @@ pub fn create_board(
             .insert(Name::new(format!("Half: {half}")))
             .with_children(|p| {
                 for j in 0..count {
-                    let (i, &card) = card_iter.next().unwrap();
+                    let i = card_iter.next().unwrap();
+                    let card = deck.get_card(i);
                     p.spawn(assets.board.node(Style {
                         min_size: Size {
                             width: Val::Px(size),
@@ -193,9 +357,13 @@ pub fn create_board(
                         .insert(Animator::new(seq(j)))
                         .insert(Name::new(format!("Card {i:?}")))
                         .insert(Idx(i, 0))
+                        .insert(InBoard(board))
                         .with_children(|p| {
-                            p.spawn(assets.spawn_card(card, size))
-                                .insert(Name::new("Card"));
+                            match faces.spawn_face(card, size) {
+                                Some(face) => p.spawn(face),
+                                None => p.spawn(assets.spawn_card(card, size)),
+                            }
+                            .insert(Name::new("Card"));
                         });
                     });
                 }
@@ -204,71 +372,115 @@ pub fn create_board(
     });
     cmd.spawn(assets.back_ground.node(Style {
         position_type: PositionType::Absolute,
+        position: UiRect {
+            left: Val::Percent(100. * lane as f32 / lanes as f32),
+        },
         flex_basis: Val::Px(0.),
         flex_shrink: 0.,
         flex_direction: FlexDirection::Row,
+        flex_wrap: FlexWrap::WrapReverse,
         justify_content: JustifyContent::Center,
         //align_content: AlignContent::FlexStart,
         align_self: AlignSelf::FlexEnd,
-        size: Size::new(Val::Percent(100.), Val::Undefined),
+        size: Size::new(Val::Percent(100. / lanes as f32), Val::Undefined),
     }))
     .insert(FocusPolicy::Pass)
     .insert(Name::new("Score Panel"))
     .insert(ScoreBoard)
+    .insert(InBoard(board))
     .with_children(|p| {
-        players.iter().enumerate().for_each(|(n, pl)| {
-            let text_bundle = |value| TextBundle {
-                style: Style {
-                    flex_basis: Val::Px(0.),
-                    align_self: AlignSelf::Center,
-                    margin: UiRect {
-                        right: Val::Px(10.),
-                        left: Val::Px(10.),
-                    },
+        let teams = deck.teams() as usize;
+        // Only the racer is on a board of a race
+        let players: Vec<(usize, &Player)> = deck
+            .seats()
+            .iter()
+            .enumerate()
+            .filter(|(n, _)| !deck.mode().race || *n as u8 == deck.player())
+            .collect();
+        // Up to 4 players in a row, rows are balanced for more
+        let rows = (players.len() as f32 / 4.).ceil().max(1.);
+        let font_size = material.size / 27. * 0.8 / rows.sqrt() / (lanes as f32).sqrt();
+        let text_bundle = |value| TextBundle {
+            style: Style {
+                flex_basis: Val::Px(0.),
+                align_self: AlignSelf::Center,
+                margin: UiRect {
+                    right: Val::Px(10.),
+                    left: Val::Px(10.),
                 },
-                text: Text {
-                    sections: vec![TextSection {
-                        value,
-                        style: TextStyle {
-                            color: Color::RED,
-                            font: assets.score_font.clone(),
-                            font_size: material.size / 27. * 0.8,
-                        },
-                    }],
-                    alignment: TextAlignment {
-                        vertical: VerticalAlign::Center,
-                        horizontal: HorizontalAlign::Center,
+            },
+            text: Text {
+                sections: vec![TextSection {
+                    value,
+                    style: TextStyle {
+                        color: Color::RED,
+                        font: assets.score_font.clone(),
+                        font_size,
                     },
+                }],
+                alignment: TextAlignment {
+                    vertical: VerticalAlign::Center,
+                    horizontal: HorizontalAlign::Center,
                 },
-            };
-            p.spawn(assets.board.node(Style {
+            },
+        };
+        for team in 0..teams {
+            let members = players.iter().filter(|(n, _)| n % teams == team);
+            let columns = (members.clone().count() as f32 / rows).ceil();
+            p.spawn(assets.back_ground.node(Style {
                 flex_direction: FlexDirection::ColumnReverse,
-                justify_content: JustifyContent::Center,
-                align_content: AlignContent::FlexStart,
+                justify_content: JustifyContent::FlexStart,
+                size: Size::new(Val::Percent(100. / teams as f32), Val::Undefined),
             }))
+            .insert(Name::new(format!("Team {}", team_name(team as u8))))
             .with_children(|p| {
-                p.spawn(text_bundle(format!(
-                    "{} {n}\nOpened: 0\nTurns: 0",
-                    if pl.is_bot() { "Bot" } else { "Human" },
-                )))
-                .insert(*pl);
-                /*
-                p.spawn(assets.board.node(Style {}))
+                if teams > 1 || deck.mode().coop.is_some() {
+                    p.spawn(text_bundle(systems::team_text(deck, team as u8)))
+                        .insert(TeamScore(team as u8))
+                        .insert(InBoard(board));
+                }
+                p.spawn(assets.back_ground.node(Style {
+                    flex_direction: FlexDirection::Row,
+                    flex_wrap: FlexWrap::WrapReverse,
+                    justify_content: JustifyContent::Center,
+                    size: Size::new(Val::Percent(100.), Val::Undefined),
+                }))
                 .with_children(|p| {
-                    for &key in ["Score", "Opened", "Turns"].iter() {
+                    members.for_each(|&(n, pl)| {
                         p.spawn(assets.board.node(Style {
-                            flex_basis: Val::Px(0.)
+                            flex_direction: FlexDirection::ColumnReverse,
+                            justify_content: JustifyContent::Center,
+                            align_content: AlignContent::FlexStart,
+                            size: Size::new(Val::Percent(100. / columns), Val::Undefined),
                         }))
-                            .with_children(|p| {
-                                p.spawn(text_bundle(key.to_string()));
-                            });
-                    }
+                        .with_children(|p| {
+                            if let Some(avatar) =
+                                seats[n].as_ref().and_then(|x| x.avatar.as_ref())
+                            {
+                                p.spawn(ImageBundle {
+                                    style: Style {
+                                        size: Size::new(
+                                            Val::Px(font_size * 2.),
+                                            Val::Px(font_size * 2.),
+                                        ),
+                                        align_self: AlignSelf::Center,
+                                    },
+                                    image: UiImage(asset_server.load(avatar.as_str())),
+                                });
+                            }
+                            p.spawn(text_bundle(format!(
+                                "{}\nOpened: 0\nTurns: 0",
+                                seats.name(n as u8, pl.is_bot()),
+                            )))
+                            .insert(*pl)
+                            .insert(InBoard(board));
+                        });
+                    });
                 });
-                */
             });
-        });
+        }
     });
-    cmd.insert_resource(deck);
+    board
 }
 fn despawn<T: Component>(mut cmd: Commands, query: Query<Entity, With<T>>) {
     for entity in query.iter() {
@@ -284,9 +496,9 @@ fn on_completion(
     time: Res<Time>,
 ) {
     if timer.duration() == Duration::ZERO {
-        timer.set_duration(Duration::from_secs(5));
         timer.pause();
     }
+    timer.set_duration(opts.motion.pace(Duration::from_secs(5)));
     if opts.outcome.is_some() {
         timer.tick(time.delta());
         if cards.iter().all(|x| x.is_visible) {
@@ -1,4 +1,8 @@
-use crate::{Deck, GameState, MemoryGAssts, MemoryGOpts, RuleSet};
+use crate::{
+    input::Control, net::NetHost, save_options, BotTier, Budget, CardPacks, ColorBlind, Deck,
+    GameState, MemoryGAssts, MemoryGOpts, Motion, Preset, Profiles, Ratings, Rematch, RuleSet,
+    ScoringPolicy, Series, Store, Themes, MAX_SEATS,
+};
 use bevy::app::AppExit;
 use bevy::prelude::*;
 use bevy_quickmenu::{style::Stylesheet, MenuItem, *};
@@ -10,8 +14,24 @@ pub enum Screens {
     NewGame,
     GameOver,
     RuleSet,
+    Scoring,
     Levels,
+    Humans,
     Bots,
+    Teams,
+    Coop,
+    Series,
+    Profiles,
+    SeatProfile(u8),
+    EditProfiles,
+    EditProfile(u8),
+    Ratings,
+    Presets,
+    Controls,
+    Speed,
+    Themes,
+    Packs,
+    ColorBlind,
 }
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
 pub enum Actions {
@@ -19,32 +39,71 @@ pub enum Actions {
     Pause,
     Quit,
     NewGame,
+    /// Next game of the series, dealt new cards
+    NextGame,
+    Rematch(Rematch),
     SetHumanFirst,
     SetDuel,
     SetCombo,
     SetFullPlate,
+    SetRace,
     SetAutoStart,
+    SetSkipAnimations,
+    /// Game speed, of the `Motion::SPEEDS`
+    SetSpeed(usize),
+    SetReducedMotion,
+    /// Theme of the board, of the `Themes`
+    SetTheme(u8),
+    /// Card pack of the faces, of the `CardPacks`, `None` for the card font
+    SetPack(Option<u8>),
+    SetColorBlind(Option<ColorBlind>),
     SetRule(RuleSet),
+    SetScoring(ScoringPolicy),
     SetLevel(u8),
+    SetHumans(u8),
     SetBots(u8),
+    SetBotTier(BotTier),
+    SetTeams(u8),
+    SetCoop(Option<Budget>),
+    SetBestOf(u8),
+    SetPreset(Preset),
+    /// Seat a profile for the nth human, `None` for the default name
+    SetProfile(u8, Option<u8>),
+    NewProfile,
+    /// Wait for the name of the profile to be typed
+    Rename(u8),
+    NextColor(u8),
+    /// Keep the level and mode picked as the preferred settings of the profile, or forget them
+    SetPreferred(u8),
+    SaveProfiles,
+    /// Wait for the key or gamepad button to bind to the control
+    Rebind(Control),
+    ResetControls,
 }
 
 impl Actions {
     fn handle_events(
         mut action_event_reader: EventReader<Self>,
         mut app_event: EventWriter<AppExit>,
-        mut commands: Commands,
         mut state: ResMut<State<GameState>>,
+        mut series: ResMut<Series>,
+        store: Res<Store>,
         menu_state: Option<Res<MenuState<Screens>>>,
     ) {
-        if let Some(menu_state) = menu_state {
-            if !action_event_reader.is_empty() {
-                commands.insert_resource(menu_state.state().0.clone());
-            }
-        }
         for event in action_event_reader.iter() {
             match event {
+                Self::SaveProfiles => {
+                    if let Some(menu_state) = &menu_state {
+                        menu_state.state().profiles.save(&store);
+                    }
+                }
                 Self::NewGame => {
+                    series.reset();
+                    state.overwrite_replace(GameState::Game).unwrap();
+                }
+                Self::NextGame => state.overwrite_replace(GameState::Game).unwrap(),
+                Self::Rematch(rematch) => {
+                    series.rematch = Some(*rematch);
                     state.overwrite_replace(GameState::Game).unwrap();
                 }
                 Self::Resume => state.pop().unwrap(),
@@ -56,20 +115,79 @@ impl Actions {
     }
 }
 
+/// Options and what the screens show of the game
+#[derive(Debug, Clone)]
+struct MenuData {
+    opts: MemoryGOpts,
+    img: Handle<Image>,
+    profiles: Profiles,
+    series: Series,
+    ratings: Ratings,
+    /// Control waiting for a key to bind
+    rebinding: Option<Control>,
+    themes: Themes,
+    packs: CardPacks,
+    /// Profile waiting for its name
+    renaming: Option<u8>,
+    /// Remote humans connected to the host, who can not join a race
+    remotes: u8,
+}
+
 impl ActionTrait for Actions {
-    type State = (MemoryGOpts, Handle<Image>);
+    type State = MenuData;
     type Event = Self;
 
     fn handle(&self, state: &mut Self::State, event_writer: &mut EventWriter<Self::Event>) {
-        let (state, ..) = state;
+        let MenuData {
+            opts: state,
+            profiles,
+            rebinding,
+            themes,
+            packs,
+            renaming,
+            ..
+        } = state;
         match self {
             Self::SetHumanFirst => state.human_first ^= true,
             Self::SetDuel => state.mode.duel ^= true,
             Self::SetCombo => state.mode.combo ^= true,
             Self::SetFullPlate => state.mode.full_plate ^= true,
+            Self::SetRace => state.mode.race ^= true,
             Self::SetAutoStart => state.auto_start ^= true,
+            Self::SetSkipAnimations => state.skip_animations ^= true,
+            Self::SetSpeed(i) => state.motion.speed = Motion::SPEEDS[*i],
+            Self::SetReducedMotion => state.motion.reduced ^= true,
+            Self::SetTheme(i) => state.theme = themes[*i as usize].name.clone(),
+            Self::SetPack(i) => state.pack = i.map(|i| packs[i as usize].name.clone()),
+            Self::SetColorBlind(palette) => state.color_blind = *palette,
             Self::SetRule(rs) => state.mode.rule = *rs,
+            Self::SetScoring(sp) => state.mode.scoring = *sp,
             Self::SetLevel(l) => state.level = *l,
+            Self::SetHumans(count) => state.players.0 = *count,
+            Self::SetBotTier(tier) => state.bot_tier = *tier,
+            Self::SetTeams(count) => state.mode.teams = *count,
+            Self::SetCoop(budget) => state.mode.coop = *budget,
+            Self::SetBestOf(games) => state.best_of = *games,
+            Self::SetPreset(preset) => preset.apply(state),
+            Self::Rebind(control) => *rebinding = Some(*control),
+            Self::ResetControls => state.bindings = default(),
+            Self::SetProfile(seat, profile) => {
+                let profile = profile.map(|p| &profiles[p as usize]);
+                if !state.seat_profile(*seat as usize, profile) {
+                    warn!("{} is seated already", profile.unwrap().name);
+                }
+            }
+            Self::NewProfile => {
+                profiles.create();
+            }
+            Self::Rename(p) => *renaming = Some(*p),
+            Self::NextColor(p) => profiles.0[*p as usize].next_color(),
+            Self::SetPreferred(p) => {
+                let profile = &mut profiles.0[*p as usize];
+                let preferred = profile.level.is_some() || profile.mode.is_some();
+                profile.level = (!preferred).then_some(state.level);
+                profile.mode = (!preferred).then_some(state.mode);
+            }
             Self::SetBots(count) => {
                 state.players.1 = *count;
                 state.human_first |= *count == 0
@@ -81,57 +199,316 @@ impl ActionTrait for Actions {
 
 impl ScreenTrait for Screens {
     type Action = Actions;
-    type State = (MemoryGOpts, Handle<Image>);
+    type State = MenuData;
 
     fn resolve(
         &self,
         state: &<<Self as ScreenTrait>::Action as bevy_quickmenu::ActionTrait>::State,
     ) -> bevy_quickmenu::Menu<Self> {
-        let (state, img) = state;
+        let MenuData {
+            opts: state,
+            img,
+            profiles,
+            series,
+            ratings,
+            rebinding,
+            themes,
+            packs,
+            renaming,
+            remotes,
+        } = state;
+        let seated = |seat: u8| state.profiles.get(seat as usize).filter(|x| !x.is_empty());
+        let seat_of = |name: &String| state.profiles.iter().position(|x| x == name);
+        let humans_action = |l| {
+            MenuItem::action(format!("{l}"), Actions::SetHumans(l)).checked(state.players.0 == l)
+        };
         let bots_action =
             |l| MenuItem::action(format!("{l}"), Actions::SetBots(l)).checked(state.players.1 == l);
+        let teams_action = |l| {
+            MenuItem::action(
+                if l == 0 { "Off".to_string() } else { format!("{l}") },
+                Actions::SetTeams(l),
+            )
+            .checked(state.mode.teams == l)
+        };
+        let coop_action = |budget: Option<Budget>| {
+            MenuItem::action(
+                budget.map_or("Off".to_string(), |b| format!("{b:?}: {}", b.desc())),
+                Actions::SetCoop(budget),
+            )
+            .checked(state.mode.coop == budget)
+        };
+        let color_blind_action = |palette: Option<ColorBlind>| {
+            MenuItem::action(
+                palette.map_or("Off".to_string(), |p| format!("{p:?}")),
+                Actions::SetColorBlind(palette),
+            )
+            .checked(state.color_blind == palette)
+        };
+        let best_of_action = |l| {
+            MenuItem::action(
+                if l == 1 { "Off".to_string() } else { format!("Best of {l}") },
+                Actions::SetBestOf(l),
+            )
+            .checked(state.best_of.max(1) == l)
+        };
+        let speed_action = |i: usize| {
+            let speed = Motion::SPEEDS[i];
+            MenuItem::action(format!("{speed}x"), Actions::SetSpeed(i))
+                .checked(state.motion.speed == speed)
+        };
         let level_action =
             |l| MenuItem::action(format!("{l}"), Actions::SetLevel(l)).checked(state.level == l);
         let rule_action = |rs| {
             MenuItem::action(format!("{rs:?}"), Actions::SetRule(rs)).checked(state.mode.rule == rs)
         };
+        let scoring_action = |sp| {
+            MenuItem::action(format!("{sp:?}"), Actions::SetScoring(sp))
+                .checked(state.mode.scoring == sp)
+        };
+        let profile_action = |seat, profile: Option<u8>| {
+            let name = profile.map(|p| &profiles[p as usize].name);
+            MenuItem::action(
+                match name.and_then(|name| Some((name, seat_of(name)?))) {
+                    None => name.map_or("Default".to_string(), |name| name.clone()),
+                    Some((name, other)) if other != seat as usize => {
+                        format!("{name} (Human {other})")
+                    }
+                    Some((name, _)) => name.clone(),
+                },
+                Actions::SetProfile(seat, profile),
+            )
+            .checked(seated(seat) == name)
+        };
         let mut menu_items = match self {
             Self::Game => vec![MenuItem::action("Pause", Actions::Pause)],
             Self::Pause => vec![
                 MenuItem::headline("Paused"),
                 MenuItem::action("Resume", Actions::Resume),
                 MenuItem::screen("New Game", Screens::NewGame),
+                MenuItem::screen("Controls", Screens::Controls),
                 MenuItem::action("Quit", Actions::Quit),
             ],
             Self::NewGame => [
                 MenuItem::headline("Recall Stones"),
-                MenuItem::action("Start!!", Actions::NewGame)
-                    .with_icon(MenuIcon::Other(img.clone())),
+                if state.mode.race && *remotes > 0 {
+                    MenuItem::label(format!("{remotes} Remote Humans Can Not Race"))
+                } else {
+                    MenuItem::action("Start!!", Actions::NewGame)
+                        .with_icon(MenuIcon::Other(img.clone()))
+                },
                 MenuItem::label(""),
                 MenuItem::label("Settings"),
+                MenuItem::screen("Presets", Screens::Presets),
                 MenuItem::action("Auto Start", Actions::SetAutoStart).checked(state.auto_start),
+                MenuItem::action("Skip Animations", Actions::SetSkipAnimations)
+                    .checked(state.skip_animations),
+                MenuItem::screen("Speed", Screens::Speed),
+                MenuItem::screen("Themes", Screens::Themes),
+                MenuItem::screen("Card Packs", Screens::Packs),
+                MenuItem::screen("Color Blind Mode", Screens::ColorBlind),
                 MenuItem::screen("Levels", Screens::Levels),
+                MenuItem::screen("Humans", Screens::Humans),
                 MenuItem::screen("Bots", Screens::Bots),
+                MenuItem::screen("Teams", Screens::Teams),
+                MenuItem::screen("Co-op", Screens::Coop),
+                MenuItem::screen("Series", Screens::Series),
+                MenuItem::screen("Profiles", Screens::Profiles),
+                MenuItem::screen("Ratings", Screens::Ratings),
+                MenuItem::screen("Controls", Screens::Controls),
                 MenuItem::screen("Rule Set", Screens::RuleSet).with_icon(MenuIcon::Controls),
+                MenuItem::screen("Scoring", Screens::Scoring),
                 MenuItem::action("Full Plate", Actions::SetFullPlate)
                     .checked(state.mode.full_plate),
             ]
             .into_iter()
+            .chain(
+                [MenuItem::action("Player First", Actions::SetHumanFirst)
+                    .checked(state.human_first)]
+                .into_iter()
+                .take(if state.players.1 > 0 { 1 } else { 0 }),
+            )
             .chain(
                 [
-                    MenuItem::action("Player First", Actions::SetHumanFirst)
-                        .checked(state.human_first),
                     MenuItem::action("Duel", Actions::SetDuel).checked(state.mode.duel),
                     MenuItem::action("Combo", Actions::SetCombo).checked(state.mode.combo),
+                    MenuItem::action("Race", Actions::SetRace).checked(state.mode.race),
                 ]
                 .into_iter()
-                .take(if state.players.1 > 0 { 3 } else { 0 }),
+                .take(if state.players.0 + state.players.1 > 1 { 3 } else { 0 }),
             )
             .collect(),
+            Self::Humans => [MenuItem::headline("Humans")]
+                .into_iter()
+                .chain((1..=MAX_SEATS - state.players.1).map(|x| humans_action(x)))
+                .collect(),
             Self::Bots => [MenuItem::headline("Bots")]
                 .into_iter()
-                .chain((0..2).map(|x| bots_action(x)))
+                .chain((0..=MAX_SEATS - state.players.0).map(|x| bots_action(x)))
+                .chain([MenuItem::label(""), MenuItem::label("Tier")])
+                .chain(BotTier::ALL.map(|tier| {
+                    MenuItem::action(
+                        format!("{tier:?}: {}", tier.desc()),
+                        Actions::SetBotTier(tier),
+                    )
+                    .checked(state.bot_tier == tier)
+                }))
+                .collect(),
+            Self::Teams => [MenuItem::headline("Teams"), teams_action(0)]
+                .into_iter()
+                .chain((2..=4).map(|x| teams_action(x)))
+                .collect(),
+            Self::Coop => vec![
+                MenuItem::headline("Co-op"),
+                coop_action(None),
+                coop_action(Some(Budget::Turns)),
+                coop_action(Some(Budget::Time)),
+            ],
+            Self::Series => [MenuItem::headline("Series")]
+                .into_iter()
+                .chain([1, 3, 5, 7].map(|x| best_of_action(x)))
+                .collect(),
+            Self::Profiles => [MenuItem::headline("Profiles")]
+                .into_iter()
+                .chain((0..state.players.0).map(|seat| {
+                    MenuItem::screen(
+                        format!(
+                            "Human {seat}: {}",
+                            seated(seat).map_or("Default", |x| x.as_str())
+                        ),
+                        Screens::SeatProfile(seat),
+                    )
+                }))
+                .chain([
+                    MenuItem::label(""),
+                    MenuItem::screen("Edit Profiles", Screens::EditProfiles),
+                ])
+                .collect(),
+            Self::EditProfiles => [MenuItem::headline("Edit Profiles")]
+                .into_iter()
+                .chain(profiles.iter().enumerate().map(|(p, profile)| {
+                    MenuItem::screen(&profile.name, Screens::EditProfile(p as u8))
+                }))
+                .chain([
+                    MenuItem::action("New Profile", Actions::NewProfile),
+                    MenuItem::action("Save Profiles", Actions::SaveProfiles),
+                ])
+                .collect(),
+            Self::EditProfile(p) => {
+                let profile = &profiles[*p as usize];
+                let [r, g, b, _] = profile.color.as_rgba_u8();
+                vec![
+                    MenuItem::headline(profile.name.clone()),
+                    MenuItem::action(
+                        match renaming {
+                            Some(x) if x == p => "Name: Type it, then press Enter".to_string(),
+                            _ => format!("Name: {}", profile.name),
+                        },
+                        Actions::Rename(*p),
+                    ),
+                    MenuItem::action(
+                        format!("Color: #{r:02x}{g:02x}{b:02x}"),
+                        Actions::NextColor(*p),
+                    ),
+                    MenuItem::action(
+                        match (profile.level, profile.mode) {
+                            (Some(level), Some(mode)) => format!(
+                                "Preferred Settings: Level {level}, {:?}, {:?}",
+                                mode.rule, mode.scoring
+                            ),
+                            _ => "Keep the Level and Mode Picked".to_string(),
+                        },
+                        Actions::SetPreferred(*p),
+                    )
+                    .checked(profile.level.is_some() || profile.mode.is_some()),
+                    MenuItem::action("Save Profiles", Actions::SaveProfiles),
+                ]
+            }
+            Self::SeatProfile(seat) => [
+                MenuItem::headline(format!("Human {seat}")),
+                profile_action(*seat, None),
+            ]
+            .into_iter()
+            .chain((0..profiles.len() as u8).map(|p| profile_action(*seat, Some(p))))
+            .collect(),
+            Self::Presets => [MenuItem::headline("Presets")]
+                .into_iter()
+                .chain(Preset::ALL.map(|preset| {
+                    MenuItem::action(
+                        format!("{preset:?}: {}", preset.desc()),
+                        Actions::SetPreset(preset),
+                    )
+                    .checked(Preset::of(state) == Some(preset))
+                }))
                 .collect(),
+            Self::Ratings => [MenuItem::headline(format!(
+                "Ratings: {:?}, Level {}",
+                state.mode.rule, state.level
+            ))]
+            .into_iter()
+            .chain(
+                ratings
+                    .table(state.mode.rule, state.level)
+                    .into_iter()
+                    .map(|r| {
+                        MenuItem::label(format!("{}: {} ({} games)", r.name, r.rating, r.games))
+                    }),
+            )
+            .collect(),
+            Self::Controls => [
+                MenuItem::headline("Controls"),
+                MenuItem::label("Pick a control, then press its key or button. Esc cancels"),
+            ]
+            .into_iter()
+            .chain(Control::ALL.map(|control| {
+                MenuItem::action(
+                    match rebinding {
+                        Some(c) if *c == control => format!("{control:?}: Press a key or button"),
+                        _ => format!("{control:?}: {}", state.bindings.desc(control)),
+                    },
+                    Actions::Rebind(control),
+                )
+            }))
+            .chain([MenuItem::action("Reset Controls", Actions::ResetControls)])
+            .collect(),
+            Self::Speed => [MenuItem::headline("Speed")]
+                .into_iter()
+                .chain((0..Motion::SPEEDS.len()).map(speed_action))
+                .chain([
+                    MenuItem::label(""),
+                    MenuItem::action("Reduced Motion", Actions::SetReducedMotion)
+                        .checked(state.motion.reduced),
+                ])
+                .collect(),
+            Self::Themes => [MenuItem::headline("Themes")]
+                .into_iter()
+                .chain(themes.iter().enumerate().map(|(i, theme)| {
+                    MenuItem::action(&theme.name, Actions::SetTheme(i as u8))
+                        .checked(themes.get(&state.theme) == theme)
+                }))
+                .collect(),
+            Self::Packs => [
+                MenuItem::headline("Card Packs"),
+                MenuItem::action("Card Font", Actions::SetPack(None)).checked(state.pack.is_none()),
+            ]
+            .into_iter()
+            .chain(packs.iter().enumerate().map(|(i, pack)| {
+                let name = if pack.tells(state.mode.rule) {
+                    pack.name.clone()
+                } else {
+                    format!("{} (Any Color only)", pack.name)
+                };
+                MenuItem::action(name, Actions::SetPack(Some(i as u8)))
+                    .checked(state.pack.as_ref() == Some(&pack.name))
+            }))
+            .collect(),
+            Self::ColorBlind => [
+                MenuItem::headline("Color Blind Mode"),
+                color_blind_action(None),
+            ]
+            .into_iter()
+            .chain(ColorBlind::ALL.map(|palette| color_blind_action(Some(palette))))
+            .collect(),
             Self::Levels => [MenuItem::headline("Levels")]
                 .into_iter()
                 .chain((0..6).map(|x| level_action(x)))
@@ -144,11 +521,60 @@ impl ScreenTrait for Screens {
                 rule_action(RuleSet::TwoDecks),
                 rule_action(RuleSet::CheckeredDeck),
             ],
-            Self::GameOver => vec![
-                MenuItem::headline(state.outcome()),
-                MenuItem::screen("New Game", Screens::NewGame),
-                MenuItem::action("Quit", Actions::Quit),
+            Self::Scoring => vec![
+                MenuItem::headline("Scoring"),
+                scoring_action(ScoringPolicy::Classic),
+                scoring_action(ScoringPolicy::FlipCount),
+                scoring_action(ScoringPolicy::Penalty),
+                scoring_action(ScoringPolicy::Streak),
             ],
+            Self::GameOver => {
+                let in_series = state.best_of > 1 && series.games() > 0;
+                let winner = series.winner(state.best_of).filter(|_| in_series);
+                [MenuItem::headline(state.outcome())]
+                    .into_iter()
+                    .chain(
+                        state
+                            .outcome
+                            .iter()
+                            .flat_map(|outcome| outcome.iter())
+                            .map(|standing| MenuItem::label(format!("{standing}"))),
+                    )
+                    .chain(ratings.changes().iter().map(|(name, delta)| {
+                        MenuItem::label(format!("{name}: {delta:+} Rating"))
+                    }))
+                    .chain(
+                        in_series
+                            .then(|| MenuItem::label(format!("Series: {}", series.standings()))),
+                    )
+                    .chain(winner.map(|e| {
+                        MenuItem::headline(format!("{} Takes the Series", series.name(e)))
+                    }))
+                    .chain(
+                        (in_series && winner.is_none())
+                            .then(|| MenuItem::action("Next Game", Actions::NextGame)),
+                    )
+                    // Races are dealt from a seed of their own, not from the series
+                    .chain(
+                        [
+                            MenuItem::action(
+                                "Rematch (Same Seed)",
+                                Actions::Rematch(Rematch::SameSeed),
+                            ),
+                            MenuItem::action(
+                                "Rematch (Swap Seats)",
+                                Actions::Rematch(Rematch::SwapSeats),
+                            ),
+                        ]
+                        .into_iter()
+                        .take(if state.mode.race { 0 } else { 2 }),
+                    )
+                    .chain([
+                        MenuItem::screen("New Game", Screens::NewGame),
+                        MenuItem::action("Quit", Actions::Quit),
+                    ])
+                    .collect()
+            }
         };
         menu_items.reverse();
         Menu::new(format!("{self:?}"), menu_items)
@@ -159,8 +585,14 @@ fn menu(
     mut commands: Commands,
     state: Res<State<GameState>>,
     opts: Option<Res<MemoryGOpts>>,
-    deck: Option<Res<Deck>>,
+    decks: Query<(), With<Deck>>,
     assets: Res<MemoryGAssts>,
+    profiles: Res<Profiles>,
+    series: Res<Series>,
+    ratings: Res<Ratings>,
+    themes: Res<Themes>,
+    packs: Res<CardPacks>,
+    host: Option<Res<NetHost>>,
     mut prev_state: Local<Option<GameState>>,
 ) {
     if prev_state.map_or(false, |x| x == *state.current()) {
@@ -173,7 +605,7 @@ fn menu(
         Screens::Game
     } else if cfg.outcome.is_some() {
         Screens::GameOver
-    } else if deck.is_none() {
+    } else if decks.is_empty() {
         Screens::NewGame
     } else {
         Screens::Pause
@@ -188,18 +620,153 @@ fn menu(
         });
 
     commands.insert_resource(MenuState::new(
-        (cfg, assets.icon.clone()),
+        MenuData {
+            opts: cfg,
+            img: assets.icon.clone(),
+            profiles: profiles.clone(),
+            series: series.clone(),
+            ratings: ratings.clone(),
+            rebinding: None,
+            themes: themes.clone(),
+            packs: packs.clone(),
+            renaming: None,
+            remotes: host.map_or(0, |host| host.remotes()),
+        },
         screen,
         Some(sheet),
     ));
 }
 
+/// Keep the options and profiles of the game in step with the menu, for every change made there
+fn sync(
+    menu_state: Option<Res<MenuState<Screens>>>,
+    mut opts: ResMut<MemoryGOpts>,
+    mut profiles: ResMut<Profiles>,
+) {
+    let Some(menu_state) = menu_state.filter(|state| state.is_changed()) else { return };
+    let MenuData {
+        opts: menu_opts,
+        profiles: menu_profiles,
+        ..
+    } = menu_state.state();
+    if *opts != *menu_opts {
+        *opts = menu_opts.clone();
+    }
+    if profiles.0 != menu_profiles.0 {
+        *profiles = menu_profiles.clone();
+    }
+}
+
+/// Keep the count of remote humans shown by the menu in step with the host
+fn count_remotes(host: Option<Res<NetHost>>, menu_state: Option<ResMut<MenuState<Screens>>>) {
+    let (Some(host), Some(mut menu_state)) = (host, menu_state) else { return };
+    let remotes = host.remotes();
+    if menu_state.state().remotes != remotes {
+        menu_state.state_mut().remotes = remotes;
+    }
+}
+
+/// Bind the next key or gamepad button pressed to the control waiting for it, Esc cancels
+fn rebind(
+    keys: Res<Input<KeyCode>>,
+    buttons: Res<Input<GamepadButton>>,
+    menu_state: Option<ResMut<MenuState<Screens>>>,
+    mut armed: Local<bool>,
+) {
+    let Some(mut menu_state) = menu_state else { return };
+    let Some(control) = menu_state.state().rebinding else {
+        *armed = false;
+        return;
+    };
+    // The key picking the control is not bound to it
+    if !std::mem::replace(&mut *armed, true) {
+        return;
+    }
+    let key = keys.get_just_pressed().next().copied();
+    let button = buttons.get_just_pressed().next().map(|b| b.button_type);
+    if key.is_none() && button.is_none() {
+        return;
+    }
+    let MenuData {
+        opts, rebinding, ..
+    } = menu_state.state_mut();
+    match (key, button) {
+        (Some(KeyCode::Escape), _) => (),
+        (Some(key), _) => opts.bindings.bind_key(control, key),
+        (_, Some(button)) => opts.bindings.bind_button(control, button),
+        _ => (),
+    }
+    *rebinding = None;
+}
+
+/// Type the name of the profile waiting for it. Enter or Esc ends, keeping the old name if the
+/// new one is empty or taken
+fn rename(
+    mut chars: EventReader<ReceivedCharacter>,
+    keys: Res<Input<KeyCode>>,
+    menu_state: Option<ResMut<MenuState<Screens>>>,
+    mut old: Local<Option<String>>,
+) {
+    let Some(mut menu_state) = menu_state else { return };
+    let Some(p) = menu_state.state().renaming else {
+        *old = None;
+        chars.clear();
+        return;
+    };
+    // The key picking the name is not typed into it
+    if old.is_none() {
+        *old = Some(menu_state.state().profiles[p as usize].name.clone());
+        chars.clear();
+        return;
+    }
+    let typed: Vec<char> = chars.iter().map(|c| c.char).collect();
+    let done = keys.any_just_pressed([KeyCode::Return, KeyCode::Escape]);
+    if typed.is_empty() && !done {
+        return;
+    }
+    let MenuData {
+        opts,
+        profiles,
+        renaming,
+        ..
+    } = menu_state.state_mut();
+    let name = &mut profiles.0[p as usize].name;
+    for c in typed {
+        match c {
+            '\u{8}' | '\u{7f}' => {
+                name.pop();
+            }
+            c if !c.is_control() && name.chars().count() < 16 => name.push(c),
+            _ => (),
+        }
+    }
+    if !done {
+        return;
+    }
+    let (old, name) = (old.take().unwrap(), name.trim().to_string());
+    let others = profiles.iter().enumerate().filter(|&(i, _)| i != p as usize);
+    let taken = name.is_empty() || others.map(|(_, x)| &x.name).any(|x| *x == name);
+    let name = if taken { old.clone() } else { name };
+    // Seated profiles keep their seats
+    opts.profiles
+        .iter_mut()
+        .filter(|x| **x == old)
+        .for_each(|x| *x = name.clone());
+    profiles.0[p as usize].name = name;
+    *renaming = None;
+}
+
 pub struct MenuPlugin;
 impl Plugin for MenuPlugin {
     fn build(&self, app: &mut App) {
         app.add_plugin(QuickMenuPlugin::<Screens>::new())
             .add_event::<Actions>()
+            .add_system(sync.before(Actions::handle_events))
             .add_system(Actions::handle_events)
+            .add_system(save_options.after(sync))
+            .add_system(rebind)
+            .add_system(rename)
+            .add_system(count_remotes)
             .add_system(menu);
     }
 }
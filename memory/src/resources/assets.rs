@@ -1,5 +1,9 @@
+use super::{ColorBlind, MemoryGOpts, Theme, Themes};
 use bevy::prelude::*;
-use bevy::render::texture::DEFAULT_IMAGE_HANDLE;
+use bevy::render::{
+    render_resource::{Extent3d, TextureDimension, TextureFormat},
+    texture::DEFAULT_IMAGE_HANDLE,
+};
 
 /// Material of a `Sprite` with a texture and color
 #[cfg_attr(feature = "dev", derive(bevy_inspector_egui::Inspectable))]
@@ -56,81 +60,122 @@ pub struct MemoryGAssts {
     pub back_ground: SpriteMaterial,
     pub score_font: Handle<Font>,
     pub card_font: Handle<Font>,
-    pub icon: Handle<Image>
+    pub icon: Handle<Image>,
+    #[cfg_attr(feature = "dev", inspectable(ignore))]
+    pub theme: Theme,
+    /// Palette for a color blindness, which also tells the decks and suits apart by shapes
+    #[cfg_attr(feature = "dev", inspectable(ignore))]
+    pub color_blind: Option<ColorBlind>,
+    /// Stripes on the cards of the second deck
+    pub pattern: Handle<Image>,
 }
 impl FromWorld for MemoryGAssts {
-    #[autodefault::autodefault(except(MemoryGAssts))]
     fn from_world(world: &mut World) -> Self {
-        let world = world.cell();
-        let asset_server = world.get_resource_mut::<AssetServer>().unwrap();
+        let (name, color_blind) = world
+            .get_resource::<MemoryGOpts>()
+            .map(|opts| (opts.theme.clone(), opts.color_blind))
+            .unwrap_or_default();
+        world.init_resource::<Themes>();
+        let theme = world.resource::<Themes>().get(&name).clone();
+        let pattern = world.resource_mut::<Assets<Image>>().add(stripes());
+        Self::themed(theme, color_blind, pattern, world.resource::<AssetServer>())
+    }
+}
+/// Diagonal stripes, to tell a card apart without its color
+fn stripes() -> Image {
+    let size = 16;
+    let data = (0..size * size)
+        .flat_map(|i| {
+            let shade = if (i % size + i / size) % 8 < 4 { 255 } else { 176 };
+            [shade, shade, shade, 255]
+        })
+        .collect();
+    Image::new(
+        Extent3d {
+            width: size,
+            height: size,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+    )
+}
+impl MemoryGAssts {
+    /// Assets in the colors and fonts of the theme, in the palette for a color blindness
+    #[autodefault::autodefault(except(MemoryGAssts))]
+    pub fn themed(
+        theme: Theme,
+        color_blind: Option<ColorBlind>,
+        pattern: Handle<Image>,
+        asset_server: &AssetServer,
+    ) -> Self {
+        let theme = theme.with_palette(color_blind);
+        let material = |color| SpriteMaterial { color };
+        let mut card = theme
+            .cards
+            .map(|(back, face)| (material(back), material(face)));
+        if color_blind.is_some() {
+            card[1].0.texture = pattern.clone();
+            card[1].1.texture = pattern.clone();
+        }
         MemoryGAssts {
-            back_ground: SpriteMaterial {
-                color: Color::rgba(0., 0., 0., 0.),
-            },
-            board: SpriteMaterial {
-                color: Color::rgb_u8(112, 112, 255),
-            },
-            card: [
-                (
-                    SpriteMaterial {
-                        color: Color::rgb_u8(130, 87, 38),
-                    },
-                    SpriteMaterial {
-                        color: Color::rgb_u8(255, 200, 155),
-                    },
-                ),
-                (
-                    SpriteMaterial {
-                        color: Color::rgb_u8(4, 86, 46),
-                    },
-                    SpriteMaterial {
-                        color: Color::rgb_u8(205, 245, 218),
-                    },
-                ),
-            ],
-            score_font: asset_server.load("fonts/FiraMono-Medium.ttf"),
-            card_font: asset_server.load("fonts/Dicier-Cards.ttf"),
+            back_ground: material(theme.background),
+            board: material(theme.board),
+            card,
+            score_font: asset_server.load(theme.score_font.as_str()),
+            card_font: asset_server.load(theme.card_font.as_str()),
             icon: asset_server.load("img/icon.png"),
+            theme,
+            color_blind,
+            pattern,
         }
     }
-}
-impl MemoryGAssts {
     pub fn count_color(&self, val: u8) -> Color {
-        match val {
-            1 => Color::GREEN,
-            2 => Color::WHITE,
-            3 => Color::YELLOW,
-            4 => Color::ORANGE,
-            _ => Color::RED,
+        let counts = &self.theme.counts;
+        match counts.get((val as usize).max(1) - 1) {
+            Some(&color) => color,
+            None => counts.last().copied().unwrap_or(Color::RED),
         }
     }
-    pub fn spawn_card(&self, val: u16, size: f32) -> TextBundle {
-        let color = if val / 14 % 2 == 0 {
-            Color::BLACK
-        } else {
-            Color::RED
-        };
+    pub fn card_text(&self, val: u8, size: f32) -> Text {
+        let color = self.theme.suits[(val / 14 % 2) as usize];
+        let mut sections = vec![TextSection {
+            value: std::char::from_u32(33 + val as u32 % 56)
+                .unwrap()
+                .to_string(),
+            style: TextStyle {
+                color,
+                font: self.card_font.clone(),
+                font_size: size,
+            },
+        }];
+        // Dot the suits of the second color, for those who can not tell it
+        if self.color_blind.is_some() && val / 14 % 2 == 1 {
+            sections.push(TextSection {
+                value: "•".to_string(),
+                style: TextStyle {
+                    color,
+                    font: self.score_font.clone(),
+                    font_size: size / 3.,
+                },
+            });
+        }
+        Text {
+            sections,
+            alignment: TextAlignment {
+                vertical: VerticalAlign::Center,
+                horizontal: HorizontalAlign::Center,
+            },
+        }
+    }
+    pub fn spawn_card(&self, val: u8, size: f32) -> TextBundle {
         TextBundle {
             style: Style {
                 flex_basis: Val::Px(0.),
                 ..Default::default()
             },
-            text: Text {
-                sections: vec![TextSection {
-                    value: std::char::from_u32(33 + val as u32 % 56)
-                        .unwrap()
-                        .to_string(),
-                    style: TextStyle {
-                        color,
-                        font: self.card_font.clone(),
-                        font_size: size,
-                    },
-                }],
-                alignment: TextAlignment {
-                    vertical: VerticalAlign::Center,
-                    horizontal: HorizontalAlign::Center,
-                },
-            },
+            text: self.card_text(val, size),
             visibility: Visibility { is_visible: false },
             ..Default::default()
         }
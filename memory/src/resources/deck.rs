@@ -1,4 +1,6 @@
 use {
+    super::Seats,
+    crate::components::{team_name, Player, PlayerOps},
     bevy::prelude::*,
     rand::{
         distributions::WeightedIndex,
@@ -9,11 +11,15 @@ use {
     std::{
         fmt::{Debug, Display, Formatter},
         ops::{Deref, DerefMut},
+        str::FromStr,
+        time::Duration,
     },
 };
 
-const CARD_MASK: u8 = (1 << 7) - 1;
-const OWN_MASK: u16 = 3 << 7;
+const CARD_MASK: u32 = (1 << 7) - 1;
+const OWN_SHIFT: u32 = 7;
+const OWN_MASK: u32 = ((1 << 8) - 1) << OWN_SHIFT;
+const COUNT_SHIFT: u32 = 15;
 
 /// Game Modes
 /// Variants
@@ -32,43 +38,307 @@ pub enum RuleSet {
     CheckeredDeck,
 }
 use RuleSet::*;
+impl FromStr for RuleSet {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        [AnyColor, SameColor, Zebra, TwoDecks, CheckeredDeck]
+            .into_iter()
+            .find(|rule| format!("{rule:?}").eq_ignore_ascii_case(&s.replace('-', "")))
+            .ok_or_else(|| {
+                format!("Unknown rule {s}, expected AnyColor, SameColor, Zebra, TwoDecks or CheckeredDeck")
+            })
+    }
+}
+/// Scoring Policies
+#[cfg_attr(feature = "dev", derive(bevy_inspector_egui::Inspectable))]
+#[derive(Hash, Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ScoringPolicy {
+    /// One point per pair
+    Classic,
+    /// Pairs score the sum of their flip counts, if one of them was flipped before or it is the final pair.
+    /// Scoring of the games saved before there was a choice
+    #[default]
+    FlipCount,
+    /// One point per pair, minus one point per mismatch
+    Penalty,
+    /// Consecutive pairs by the same player score 1, 2, 3...
+    Streak,
+}
+use ScoringPolicy::*;
+impl ScoringPolicy {
+    pub fn desc(&self) -> &str {
+        match self {
+            Classic => "One point per pair",
+            FlipCount => "Pairs score their flip counts",
+            Penalty => "One point per pair, minus one per mismatch",
+            Streak => "Consecutive pairs score more",
+        }
+    }
+}
+/// Budget of a co-op game
+#[cfg_attr(feature = "dev", derive(bevy_inspector_egui::Inspectable))]
+#[derive(Hash, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Budget {
+    /// Two turns per pair, taken by everyone together
+    Turns,
+    /// Five seconds per pair
+    Time,
+}
+impl Budget {
+    pub fn desc(&self) -> &str {
+        match self {
+            Budget::Turns => "Two turns per pair",
+            Budget::Time => "Five seconds per pair",
+        }
+    }
+    /// Turns, or seconds, to clear the pairs in
+    pub fn limit(&self, pairs: usize) -> u32 {
+        match self {
+            Budget::Turns => 2 * pairs as u32,
+            Budget::Time => 5 * pairs as u32,
+        }
+    }
+}
+/// How much of the cards flipped before a bot remembers
+#[cfg_attr(feature = "dev", derive(bevy_inspector_egui::Inspectable))]
+#[derive(Hash, Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BotTier {
+    /// Flips at random
+    #[default]
+    Forgetful,
+    /// Flips the mate of its first card, once seen
+    Attentive,
+    /// Also starts its turn with a pair it has seen
+    Sharp,
+}
+impl BotTier {
+    pub const ALL: [BotTier; 3] = [BotTier::Forgetful, BotTier::Attentive, BotTier::Sharp];
+    pub fn desc(&self) -> &str {
+        match self {
+            BotTier::Forgetful => "Flips at random",
+            BotTier::Attentive => "Finds the mate of its first card",
+            BotTier::Sharp => "Remembers every card flipped",
+        }
+    }
+}
 #[cfg_attr(feature = "dev", derive(bevy_inspector_egui::Inspectable))]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Resource)]
 pub struct Mode {
     pub rule: RuleSet,
+    #[serde(default)]
+    pub scoring: ScoringPolicy,
     pub combo: bool,
     pub full_plate: bool,
     pub duel: bool,
+    /// Number of teams, with seats alternating between them. Below 2 everyone plays for themselves
+    #[serde(default)]
+    pub teams: u8,
+    /// Every player clears a board of their own, all dealt the same cards
+    #[serde(default)]
+    pub race: bool,
+    /// Everyone shares one score, clearing the board together within the budget
+    #[serde(default)]
+    #[cfg_attr(feature = "dev", inspectable(ignore))]
+    pub coop: Option<Budget>,
 }
 impl Default for Mode {
     fn default() -> Self {
         Self {
             rule: Zebra,
+            scoring: FlipCount,
             combo: true,
             full_plate: true,
             duel: false,
+            teams: 0,
+            race: false,
+            coop: None,
+        }
+    }
+}
+/// Standing of a seat at the end of a game
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Standing {
+    pub name: String,
+    pub seat: u8,
+    pub bot: bool,
+    #[serde(default)]
+    pub team: Option<u8>,
+    /// Placement starting from 1, tied seats share a place
+    pub place: u8,
+    pub score: i16,
+    pub turns: u8,
+}
+impl Display for Standing {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}. {}", self.place, self.name)?;
+        if let Some(team) = self.team {
+            write!(f, " (Team {})", team_name(team))?;
+        }
+        write!(f, " - Score: {}, Turns: {}", self.score, self.turns)
+    }
+}
+/// Result of a completed game, with standings ordered by placement
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Outcome {
+    standings: Vec<Standing>,
+    /// Stars earned together in a co-op game
+    #[serde(default)]
+    stars: Option<u8>,
+}
+impl Outcome {
+    /// Seats are placed by the total score of their team, or their own without teams
+    pub fn new(seats: &[Player], scores: &[i16], teams: u8) -> Self {
+        let group = |pl: &Player| if teams > 1 { pl.team(teams) } else { pl.id() };
+        let totals: Vec<i16> = (0..if teams > 1 { teams } else { seats.len() as u8 })
+            .map(|g| {
+                seats
+                    .iter()
+                    .zip(scores)
+                    .filter(|(pl, _)| group(pl) == g)
+                    .map(|(_, &x)| x)
+                    .sum()
+            })
+            .collect();
+        let mut standings: Vec<Standing> = seats
+            .iter()
+            .zip(scores)
+            .map(|(pl, &score)| Standing {
+                name: format!("{} {}", if pl.is_bot() { "Bot" } else { "Human" }, pl.id()),
+                seat: pl.id(),
+                bot: pl.is_bot(),
+                team: (teams > 1).then(|| pl.team(teams)),
+                place: 1 + totals
+                    .iter()
+                    .filter(|&&x| x > totals[group(pl) as usize])
+                    .count() as u8,
+                score,
+                turns: pl.deref().1,
+            })
+            .collect();
+        standings.sort_by_key(|s| (s.place, s.team, s.seat));
+        Self {
+            standings,
+            stars: None,
+        }
+    }
+    /// Everyone shares the place in co-op, with no stars for running out of the budget
+    pub fn coop(seats: &[Player], scores: &[i16], stars: u8) -> Self {
+        let mut outcome = Self::new(seats, scores, 1);
+        outcome.standings.iter_mut().for_each(|s| s.place = 1);
+        outcome.standings.sort_by_key(|s| s.seat);
+        outcome.stars = Some(stars);
+        outcome
+    }
+    /// Racers who cleared their board are placed first, then by fewer turns
+    pub fn race(racers: &[(Player, i16, bool)]) -> Self {
+        let key = |&(pl, _, done): &(Player, i16, bool)| (!done, pl.deref().1);
+        let mut standings: Vec<Standing> = racers
+            .iter()
+            .map(|racer @ &(pl, score, _)| Standing {
+                name: format!("{} {}", if pl.is_bot() { "Bot" } else { "Human" }, pl.id()),
+                seat: pl.id(),
+                bot: pl.is_bot(),
+                team: None,
+                place: 1 + racers.iter().filter(|x| key(x) < key(racer)).count() as u8,
+                score,
+                turns: pl.deref().1,
+            })
+            .collect();
+        standings.sort_by_key(|s| (s.place, s.seat));
+        Self {
+            standings,
+            stars: None,
         }
     }
+    /// Use the names of the seated profiles
+    pub fn with_names(mut self, seats: &Seats) -> Self {
+        self.standings
+            .iter_mut()
+            .for_each(|s| s.name = seats.name(s.seat, s.bot));
+        self
+    }
+    pub fn winners(&self) -> impl Iterator<Item = &Standing> {
+        self.iter().filter(|s| s.place == 1)
+    }
+    /// Teams in the first place
+    pub fn winning_teams(&self) -> Vec<u8> {
+        let mut teams: Vec<u8> = self.winners().filter_map(|s| s.team).collect();
+        teams.dedup();
+        teams
+    }
+    /// More than one seat, or team, shares the first place
+    pub fn is_shared(&self) -> bool {
+        match self.winning_teams().len() {
+            0 => self.winners().count() > 1,
+            teams => teams > 1,
+        }
+    }
+    /// Every seat shares the first place
+    pub fn is_draw(&self) -> bool {
+        self.len() > 1 && self.iter().all(|s| s.place == 1) && self.winning_teams().len() != 1
+    }
+    pub fn standing(&self, seat: u8) -> Option<&Standing> {
+        self.iter().find(|s| s.seat == seat)
+    }
+    /// Stars of a co-op game, where none is running out of the budget
+    pub fn stars(&self) -> Option<u8> {
+        self.stars
+    }
 }
-/// Deck
+impl Deref for Outcome {
+    type Target = Vec<Standing>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.standings
+    }
+}
+/// Deck of a board. Must be used as a component on the `Board`
 #[cfg_attr(feature = "dev", derive(bevy_inspector_egui::Inspectable, Default))]
-#[derive(Debug, Clone, Resource)]
+#[derive(Debug, Clone, Component)]
 pub struct Deck {
     mode: Mode,
     /// Map of cards, where each entry is
     /// card with its value in 7 bits,
-    /// player who opened it in 2 bits,
-    /// number of times opened in 7 bits.
-    map: Vec<u16>,
+    /// player who opened it in 8 bits,
+    /// number of times opened in the remaining 17 bits.
+    map: Vec<u32>,
     players: (u8, u8),
-    outcome: Option<u8>,
+    #[cfg_attr(feature = "dev", inspectable(ignore))]
+    seats: Vec<Player>,
+    #[cfg_attr(feature = "dev", inspectable(ignore))]
+    outcome: Option<Outcome>,
+    /// Pairs found in a row by the current player
+    streak: u8,
+    /// Turns taken by each team, to rotate through its members
+    team_turns: Vec<usize>,
+    /// Time played, spent from the budget of a co-op game
+    #[cfg_attr(feature = "dev", inspectable(ignore))]
+    elapsed: Duration,
     pub opened: Vec<usize>,
-    pub scores: Vec<u16>,
+    pub scores: Vec<i16>,
 }
 impl Deck {
     /// Randomize couplets till max count and initialize them in the Deck
-    pub fn init((count, max): (u8, u8), mode: Mode, players: u8) -> Self {
-        let mut rng = rand::thread_rng();
+    pub fn init(params: (u8, u8), mode: Mode, seats: &[Player]) -> Self {
+        Self::seeded(params, mode, seats, random())
+    }
+    /// Deck of a race, where only the racer on the seat plays
+    pub fn racing(params: (u8, u8), mode: Mode, seats: &[Player], seat: u8, seed: u64) -> Self {
+        Self::seeded(params, mode, seats, seed).starting_with(seat)
+    }
+    /// Deck where the seat takes the first turn
+    pub fn starting_with(mut self, seat: u8) -> Self {
+        let teams = self.teams();
+        self.team_turns = (0..teams)
+            .map(|t| if t == seat % teams { (seat / teams) as usize + 1 } else { 0 })
+            .collect();
+        self.players.0 = seat;
+        self
+    }
+    /// Decks from the same seed are dealt the same cards
+    pub fn seeded((count, max): (u8, u8), mode: Mode, seats: &[Player], seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
         let suites = match mode.rule {
             TwoDecks | CheckeredDeck => 4,
             _ => 2,
@@ -82,12 +352,12 @@ impl Deck {
                 dist.update_weights(&[(idx, &0)]).unwrap();
             }
         }
-        let map_func = |(card, count): (usize, &usize)| -> Vec<u16> {
+        let map_func = |(card, count): (usize, &usize)| -> Vec<u32> {
             {
                 match mode.rule {
                     AnyColor => sample(&mut rng, 4, count * 2)
                         .iter()
-                        .map(|x| (x * 14 + card) as u16)
+                        .map(|x| (x * 14 + card) as u32)
                         .collect(),
                     SameColor | Zebra => sample(&mut rng, 4, 1)
                         .iter()
@@ -102,17 +372,17 @@ impl Deck {
                                 })
                                 .collect::<Vec<usize>>()
                         })
-                        .map(|x| (x * 14 + card) as u16)
+                        .map(|x| (x * 14 + card) as u32)
                         .collect(),
                     TwoDecks | CheckeredDeck => sample(&mut rng, 4, *count)
                         .iter()
                         .flat_map(|x| [x, if mode.rule == TwoDecks { x } else { x + 4 }])
-                        .map(|x| (x * 14 + card) as u16)
+                        .map(|x| (x * 14 + card) as u32)
                         .collect(),
                 }
             }
         };
-        let mut map: Vec<u16> = cards.iter().enumerate().flat_map(map_func).collect();
+        let mut map: Vec<u32> = cards.iter().enumerate().flat_map(map_func).collect();
         if mode.full_plate {
             map.shuffle(&mut rng);
         } else {
@@ -125,11 +395,26 @@ impl Deck {
             map[0..count as usize].shuffle(&mut rng);
             map[count as usize..2 * count as usize].shuffle(&mut rng);
         }
+        Self::new(mode, map, seats)
+    }
+    /// Deck with only the backs of the cards known, the faces are revealed as they are flipped
+    pub fn masked(mode: Mode, cards: &[u8], seats: &[Player]) -> Self {
+        Self::new(mode, cards.iter().map(|&x| x as u32).collect(), seats)
+    }
+    fn new(mode: Mode, map: Vec<u32>, seats: &[Player]) -> Self {
+        let players = seats.len() as u8;
         Self {
             mode,
             map,
             players: (0, players),
+            seats: seats.to_vec(),
             outcome: None,
+            streak: 0,
+            elapsed: Duration::ZERO,
+            // Everyone is on one team in co-op
+            team_turns: (0..if mode.coop.is_some() { 1 } else { mode.teams.clamp(1, players) })
+                .map(|t| (t == 0) as usize)
+                .collect(),
             opened: vec![],
             scores: vec![0; players as usize],
         }
@@ -139,9 +424,11 @@ impl Deck {
         if self.opened.len() == 1 {
             return false;
         }
-        let l = self.get_card(self.opened[0]);
-        let r = self.get_card(self.opened[1]);
-        // println!("{l} {r}");
+        self.pairs(self.opened[0], self.opened[1])
+    }
+    /// Whether the cards make a pair under the rule set
+    fn pairs(&self, l: usize, r: usize) -> bool {
+        let (l, r) = (self.get_card(l), self.get_card(r));
         let eq = l % 14 == r % 14;
         match self.mode.rule {
             AnyColor => eq,
@@ -153,16 +440,42 @@ impl Deck {
     }
 
     pub fn set_next_player(&mut self) {
-        if self.completed() {
+        if self.completed() || self.mode.race {
             return;
         }
         let end_turn = self.opened.len() == 2;
         let duel = self.mode.duel;
         let combo = end_turn && self.match_found() && self.mode.combo;
         if end_turn && !(combo ^ duel) || !end_turn && duel {
-            self.players.0 = (self.players.0 + 1) % self.players.1
+            self.players.0 = self.next_seat();
+            self.streak = 0;
         }
     }
+    /// Next member of the next team, where everyone is in one team without teams
+    fn next_seat(&mut self) -> u8 {
+        let teams = self.teams();
+        let team = (self.players.0 % teams + 1) % teams;
+        let members: Vec<u8> = (team..self.players.1).step_by(teams as usize).collect();
+        let turns = &mut self.team_turns[team as usize];
+        *turns += 1;
+        members[(*turns - 1) % members.len()]
+    }
+    pub fn teams(&self) -> u8 {
+        self.team_turns.len() as u8
+    }
+    pub fn team_scores(&self) -> Vec<i16> {
+        let teams = self.teams();
+        (0..teams)
+            .map(|t| {
+                self.scores
+                    .iter()
+                    .enumerate()
+                    .filter(|(seat, _)| *seat as u8 % teams == t)
+                    .map(|(_, &x)| x)
+                    .sum()
+            })
+            .collect()
+    }
 
     pub fn is_revealed(&self, mv: usize) -> bool {
         self.get_owner(mv) > 0
@@ -182,52 +495,174 @@ impl Deck {
         }
 
         self.opened.push(mv);
-        self[mv] += 1 << 9;
+        self[mv] += 1 << COUNT_SHIFT;
+        if self.opened.len() == 2 {
+            let player = self.player() as usize;
+            self.seats[player].inc_turn();
+        }
 
         if self.opened.len() == 2 && self.match_found() {
-            let player = (self.player() as u16 + 1) << 7;
+            let player = (self.player() as u32 + 1) << OWN_SHIFT;
             let pmv = self.opened[0];
             self[pmv] += player;
             self[mv] += player;
-            let outcome = self.iter().all(|&x| (x & 3 << 7) > 0);
-            if outcome || self.get_count(mv) > 1 {
-                let score = self.get_count(pmv) + self.get_count(mv);
-                let player = self.player() as usize;
-                self.scores[player] += score as u16;
-            }
+            self.streak += 1;
+            let outcome = self.iter().all(|&x| (x & OWN_MASK) > 0);
+            let score = match self.mode.scoring {
+                Classic | Penalty => 1,
+                FlipCount if outcome || self.get_count(mv) > 1 => {
+                    (self.get_count(pmv) + self.get_count(mv)) as i16
+                }
+                FlipCount => 0,
+                Streak => self.streak as i16,
+            };
+            let player = self.player() as usize;
+            self.scores[player] += score;
             if outcome {
-                self.outcome =  self.scores.iter().position(|x| x==self.scores.iter().max().unwrap()).map(|x|x as u8);
+                self.finish(true);
+            }
+        } else if self.opened.len() == 2 {
+            self.streak = 0;
+            if self.mode.scoring == Penalty {
+                let player = self.player() as usize;
+                self.scores[player] -= 1;
             }
         }
+        if self.opened.len() == 2 && !self.completed() && self.spent() >= Some(1.) {
+            self.finish(false);
+        }
         self.set_next_player()
     }
+    /// Move of a bot of the tier, out of the available ones
+    pub fn bot_move(&self, tier: BotTier, rng: &mut impl Rng) -> Option<usize> {
+        let available: Vec<usize> = (0..self.len())
+            .filter(|&mv| self.is_available_move(mv))
+            .collect();
+        let seen: Vec<usize> = available
+            .iter()
+            .copied()
+            .filter(|&mv| self.get_count(mv) > 0)
+            .collect();
+        let mate = |l: usize| seen.iter().copied().find(|&r| r != l && self.pairs(l, r));
+        let known = match (tier, self.opened.len()) {
+            (BotTier::Forgetful, _) => None,
+            (_, 1) => mate(self.opened[0]),
+            (BotTier::Sharp, _) => seen.iter().copied().find(|&l| mate(l).is_some()),
+            _ => None,
+        };
+        known.or_else(|| available.choose(rng).copied())
+    }
+    /// Take back the first card of the turn, before the second one is flipped.
+    /// Its face is seen by then, so the turn is over, as if the pair was missed.
+    /// Not in a duel, where the turn passes on with the first card
+    pub fn undo(&mut self) -> Option<usize> {
+        if self.completed() || self.mode.duel || self.opened.len() != 1 {
+            return None;
+        }
+        let mv = self.opened.pop()?;
+        let player = self.player() as usize;
+        self.seats[player].inc_turn();
+        self.streak = 0;
+        if self.mode.scoring == Penalty {
+            self.scores[player] -= 1;
+        }
+        if self.spent() >= Some(1.) {
+            self.finish(false);
+        } else if !self.mode.race {
+            self.players.0 = self.next_seat();
+        }
+        Some(mv)
+    }
+    fn finish(&mut self, cleared: bool) {
+        self.outcome = Some(match self.spent() {
+            Some(spent) => {
+                let stars = match spent {
+                    _ if !cleared => 0,
+                    x if x <= 0.5 => 3,
+                    x if x <= 0.75 => 2,
+                    _ => 1,
+                };
+                Outcome::coop(&self.seats, &self.scores, stars)
+            }
+            None => Outcome::new(&self.seats, &self.scores, self.teams()),
+        });
+    }
+    /// Run the clock, where a co-op game against time ends once it runs out
+    pub fn tick(&mut self, delta: Duration) {
+        if self.completed() {
+            return;
+        }
+        self.elapsed += delta;
+        if self.mode.coop == Some(Budget::Time) && self.spent() >= Some(1.) {
+            self.finish(false);
+        }
+    }
+    /// Turns taken by everyone
+    pub fn turns(&self) -> u32 {
+        self.seats.iter().map(|pl| pl.deref().1 as u32).sum()
+    }
+    /// Share of the co-op budget spent
+    pub fn spent(&self) -> Option<f32> {
+        let left = self.budget_left()?;
+        let limit = self.mode.coop?.limit(self.len() / 2);
+        Some(1. - left as f32 / limit as f32)
+    }
+    /// Turns, or seconds, left of the co-op budget
+    pub fn budget_left(&self) -> Option<u32> {
+        let budget = self.mode.coop?;
+        let spent = match budget {
+            Budget::Turns => self.turns(),
+            Budget::Time => self.elapsed.as_secs() as u32,
+        };
+        Some(budget.limit(self.len() / 2).saturating_sub(spent))
+    }
 
     pub fn player(&self) -> u8 {
         self.players.0
     }
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+    pub fn seats(&self) -> &[Player] {
+        &self.seats
+    }
 
     pub fn completed(&self) -> bool {
         self.outcome.is_some()
     }
-    pub fn outcome(&self) -> Option<u8> {
-        self.outcome
+    pub fn outcome(&self) -> Option<&Outcome> {
+        self.outcome.as_ref()
     }
 
-    fn _get(&self, idx: usize) -> u16 {
+    fn _get(&self, idx: usize) -> u32 {
         self[idx]
     }
     pub fn get_owner(&self, idx: usize) -> u8 {
-        ((self[idx] & OWN_MASK) >> 7) as u8
+        ((self[idx] & OWN_MASK) >> OWN_SHIFT) as u8
     }
     pub fn get_card(&self, idx: usize) -> u8 {
-        self[idx] as u8 & CARD_MASK
+        (self[idx] & CARD_MASK) as u8
     }
     pub fn get_count(&self, idx: usize) -> u8 {
-        (self[idx] >> 9) as u8
+        (self[idx] >> COUNT_SHIFT) as u8
+    }
+    /// Cards with their faces hidden, only telling the backs apart
+    pub fn masked_cards(&self) -> Vec<u8> {
+        (0..self.len()).map(|i| self.get_card(i) / 56 * 56).collect()
+    }
+    /// Set the face of a masked card
+    pub fn reveal(&mut self, idx: usize, card: u8) {
+        self[idx] = self[idx] & !CARD_MASK | card as u32;
+    }
+    /// Number of cards revealed by the player
+    pub fn opened_by(&self, player: u8) -> usize {
+        (0..self.len())
+            .filter(|&i| self.get_owner(i) == player + 1)
+            .count()
     }
 }
 impl Deref for Deck {
-    type Target = Vec<u16>;
+    type Target = Vec<u32>;
 
     fn deref(&self) -> &Self::Target {
         &self.map
@@ -260,13 +695,15 @@ impl Display for Deck {
 }
 impl Mode {
     pub fn desc(&self) -> String {
-        format!("Rule: {}\nCombo: {}\nAccess:{}",match self.rule {
+        format!("Rule: {}\nScoring: {}\nCombo: {}\nAccess:{}",match self.rule {
             AnyColor => "Pairs need only to be of same rank",
             SameColor => "Pairs need to be of same rank and color",
             Zebra => "Pairs need to be of same rank but color should be of opposite",
             TwoDecks => "Pairs need to be of same rank and suite",
             CheckeredDeck => "Pairs need to be of same rank and suite,\ncards have different backs for easy differentiation",
-        },if self.combo {"Allowed"}else{"One Flip per turn"},if self.full_plate {"Full Plate"}else{"Half Plate"})
+        },self.scoring.desc(),if self.combo {"Allowed"}else{"One Flip per turn"},if self.full_plate {"Full Plate"}else{"Half Plate"})
+            + if self.race { "\nRace: Everyone on their own board" } else { "" }
+            + &self.coop.map_or(String::new(), |b| format!("\nCo-op: {}", b.desc()))
     }
     pub fn example(&self) -> &str {
         match self.rule {
@@ -278,3 +715,167 @@ impl Mode {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::components::Flesh};
+
+    fn deck(mode: Mode, cards: &[u8], seats: u8) -> Deck {
+        let seats: Vec<Player> = (0..seats).map(|s| Flesh(s, 0).into()).collect();
+        Deck::masked(mode, cards, &seats)
+    }
+    fn scoring(scoring: ScoringPolicy) -> Mode {
+        Mode {
+            rule: AnyColor,
+            scoring,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn scoring_defaults_to_flip_count() {
+        let mode: Mode =
+            ron::from_str("(rule: Zebra, combo: true, full_plate: true, duel: false)").unwrap();
+        assert_eq!(mode.scoring, FlipCount);
+    }
+    #[test]
+    fn classic_scores_a_point_per_pair() {
+        let mut deck = deck(scoring(Classic), &[0, 14, 1, 15], 2);
+        [0, 1, 2, 3].into_iter().for_each(|mv| deck.play(mv));
+        assert_eq!(deck.scores, [2, 0]);
+        assert!(deck.completed());
+    }
+    #[test]
+    fn penalty_takes_a_point_per_mismatch() {
+        let mut deck = deck(scoring(Penalty), &[0, 1, 14, 15], 2);
+        [0, 1, 0, 2].into_iter().for_each(|mv| deck.play(mv));
+        assert_eq!(deck.scores, [-1, 1]);
+    }
+    #[test]
+    fn flip_count_scores_cards_flipped_before() {
+        let mut deck = deck(scoring(FlipCount), &[0, 1, 14, 15], 2);
+        [0, 1, 2, 0].into_iter().for_each(|mv| deck.play(mv));
+        assert_eq!(deck.scores, [0, 3]);
+        // The final pair scores even if flipped for the first time
+        [1, 3].into_iter().for_each(|mv| deck.play(mv));
+        assert_eq!(deck.scores, [0, 6]);
+    }
+    #[test]
+    fn streak_scores_more_for_pairs_in_a_row() {
+        let mut deck = deck(scoring(Streak), &[0, 14, 1, 15, 2, 16], 2);
+        (0..6).for_each(|mv| deck.play(mv));
+        assert_eq!(deck.scores, [6, 0]);
+    }
+    #[test]
+    fn owners_fit_eight_seats_beside_the_second_deck() {
+        let mode = Mode {
+            rule: TwoDecks,
+            ..Default::default()
+        };
+        let mut deck = deck(mode, &[111, 111], 8).starting_with(7);
+        [0, 1].into_iter().for_each(|mv| deck.play(mv));
+        assert_eq!(
+            (deck.get_card(1), deck.get_owner(1), deck.get_count(1)),
+            (111, 8, 1)
+        );
+        assert_eq!(deck.opened_by(7), 2);
+        assert_eq!(deck.scores[7], 2);
+    }
+    #[test]
+    fn turns_alternate_between_teams() {
+        let mode = Mode {
+            teams: 2,
+            ..scoring(Classic)
+        };
+        let mut deck = deck(mode, &[0, 1, 14, 15], 4);
+        let order: Vec<u8> = (0..5)
+            .map(|_| {
+                let player = deck.player();
+                [0, 1].into_iter().for_each(|mv| deck.play(mv));
+                player
+            })
+            .collect();
+        assert_eq!(order, [0, 1, 2, 3, 0]);
+    }
+    #[test]
+    fn teams_share_their_score_and_place() {
+        let mode = Mode {
+            teams: 2,
+            ..scoring(Classic)
+        };
+        let mut deck = deck(mode, &[0, 1, 14, 15], 4);
+        deck.scores = vec![1, 0, 0, 2];
+        assert_eq!(deck.team_scores(), [1, 2]);
+        let outcome = Outcome::new(deck.seats(), &deck.scores, deck.teams());
+        assert_eq!(outcome.winning_teams(), [1]);
+        assert!(!outcome.is_shared());
+        let places: Vec<(u8, u8)> = outcome.iter().map(|s| (s.seat, s.place)).collect();
+        assert_eq!(places, [(1, 1), (3, 1), (0, 2), (2, 2)]);
+    }
+    fn coop(budget: Budget) -> Mode {
+        Mode {
+            coop: Some(budget),
+            ..scoring(Classic)
+        }
+    }
+    #[test]
+    fn coop_stars_follow_the_budget_spent() {
+        // Two pairs give a budget of four turns
+        for (moves, stars) in [
+            (&[0, 1, 2, 3][..], 3),
+            (&[0, 2, 0, 1, 2, 3][..], 2),
+            (&[0, 2, 0, 2, 0, 1, 2, 3][..], 1),
+        ] {
+            let mut deck = deck(coop(Budget::Turns), &[0, 14, 1, 15], 2);
+            moves.iter().for_each(|&mv| deck.play(mv));
+            let outcome = deck.outcome().unwrap();
+            assert_eq!(outcome.stars(), Some(stars));
+            assert_eq!(outcome.winners().count(), 2);
+        }
+    }
+    #[test]
+    fn coop_fails_once_the_turns_run_out() {
+        let mut deck = deck(coop(Budget::Turns), &[0, 14, 1, 15], 2);
+        (0..4).for_each(|_| [0, 2].into_iter().for_each(|mv| deck.play(mv)));
+        assert_eq!(deck.outcome().and_then(Outcome::stars), Some(0));
+    }
+    #[test]
+    fn coop_fails_once_the_time_runs_out() {
+        let mut deck = deck(coop(Budget::Time), &[0, 14, 1, 15], 2);
+        deck.tick(Duration::from_secs(9));
+        assert!(!deck.completed());
+        deck.tick(Duration::from_secs(1));
+        assert_eq!(deck.outcome().and_then(Outcome::stars), Some(0));
+    }
+    #[test]
+    fn bots_remember_by_their_tier() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut deck = deck(scoring(Classic), &[0, 14, 1, 15], 2);
+        [0, 2, 1].into_iter().for_each(|mv| deck.play(mv));
+        assert_eq!(deck.bot_move(BotTier::Attentive, &mut rng), Some(0));
+        deck.play(3);
+        // Every card was seen, so the turn starts with a pair
+        assert_eq!(deck.bot_move(BotTier::Sharp, &mut rng), Some(0));
+        assert_eq!(deck.bot_move(BotTier::Attentive, &mut rng).map(|mv| mv < 4), Some(true));
+    }
+    #[test]
+    fn taking_back_a_card_costs_the_turn() {
+        let mut deck = deck(scoring(Penalty), &[0, 14, 1, 15], 2);
+        deck.play(0);
+        assert_eq!(deck.undo(), Some(0));
+        assert!(deck.opened.is_empty() && deck.is_available_move(0));
+        // The card stays counted, as its face was seen
+        assert_eq!(deck.get_count(0), 1);
+        assert_eq!((deck.player(), deck.turns(), deck.scores[0]), (1, 1, -1));
+        assert_eq!(deck.undo(), None);
+    }
+    #[test]
+    fn taking_back_a_card_spends_the_coop_budget() {
+        let mut deck = deck(coop(Budget::Turns), &[0, 14, 1, 15], 2);
+        (0..4).for_each(|_| {
+            deck.play(0);
+            deck.undo();
+        });
+        assert_eq!(deck.outcome().and_then(Outcome::stars), Some(0));
+    }
+}
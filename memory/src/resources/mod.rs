@@ -1,5 +1,15 @@
-pub use {assets::*, deck::*, options::*};
+pub use {
+    assets::*, deck::*, options::*, packs::*, pads::*, profiles::*, ratings::*, series::*,
+    storage::*, theme::*,
+};
 
 mod assets;
 pub(crate) mod deck;
 mod options;
+mod packs;
+mod pads;
+mod profiles;
+mod ratings;
+mod series;
+mod storage;
+mod theme;
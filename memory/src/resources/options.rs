@@ -1,15 +1,22 @@
 use {
-    super::{Mode, RuleSet::*},
-    crate::components::*,
+    super::{
+        BotTier, Budget, ColorBlind, Mode, Outcome, Profile, Profiles, RuleSet::*, ScoringPolicy,
+        Seats, Store,
+    },
+    crate::{components::*, input::Bindings, net::NetClient},
     bevy::prelude::*,
     rand::{distributions::WeightedIndex, prelude::*},
     serde::{Deserialize, Serialize},
+    std::time::Duration,
 };
 
+/// Most seats a game can have, humans and bots combined
+pub const MAX_SEATS: u8 = 8;
+
 /// Board generation options. Must be used as a resource
 // We use serde to allow saving option presets and loading them at runtime
 #[cfg_attr(feature = "dev", derive(bevy_inspector_egui::Inspectable))]
-#[derive(Debug, Clone, Serialize, Deserialize, Resource)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Resource)]
 pub struct MemoryGOpts {
     /// Padding between cards
     pub card_padding: f32,
@@ -18,10 +25,47 @@ pub struct MemoryGOpts {
     #[cfg_attr(feature = "dev", inspectable(min = 0, max = 5))]
     pub level: u8,
     //#[cfg_attr(feature="dev",inspectable(min = (1,0), max = (2,1)))]
+    /// Humans and Bots, together no more than `MAX_SEATS`
     pub players: (u8, u8),
+    /// How much the bots remember
+    #[serde(default)]
+    pub bot_tier: BotTier,
     pub human_first: bool,
-    pub outcome: Option<u8>,
+    /// Names of the profiles seated, in order of the humans
+    #[serde(default)]
+    #[cfg_attr(feature = "dev", inspectable(ignore))]
+    pub profiles: Vec<String>,
+    #[serde(skip)]
+    #[cfg_attr(feature = "dev", inspectable(ignore))]
+    pub outcome: Option<Outcome>,
     pub auto_start: bool,
+    /// Games of a match series, the first to win the majority takes it. Below 2 there is no series
+    #[serde(default)]
+    pub best_of: u8,
+    /// Keys and gamepad buttons of the controls
+    #[serde(default)]
+    #[cfg_attr(feature = "dev", inspectable(ignore))]
+    pub bindings: Bindings,
+    /// Play on while the cards turn, instead of waiting for them to settle
+    #[serde(default)]
+    pub skip_animations: bool,
+    /// Speed of the animations and the bots
+    #[serde(default)]
+    pub motion: Motion,
+    /// Name of the theme, the default one if not found
+    #[serde(default)]
+    pub theme: String,
+    /// Name of the card pack of the faces, the card font if none
+    #[serde(default)]
+    pub pack: Option<String>,
+    /// Palette and shape cues for a color blindness
+    #[serde(default)]
+    #[cfg_attr(feature = "dev", inspectable(ignore))]
+    pub color_blind: Option<ColorBlind>,
+    /// Seed of the next deal, to play the same cards again
+    #[serde(skip)]
+    #[cfg_attr(feature = "dev", inspectable(ignore))]
+    pub seed: Option<u64>,
 }
 impl Default for MemoryGOpts {
     fn default() -> Self {
@@ -30,17 +74,194 @@ impl Default for MemoryGOpts {
             card_padding: 3.,
             mode: Mode {
                 rule: Zebra,
+                scoring: ScoringPolicy::FlipCount,
                 combo: true,
                 full_plate: true,
                 duel: false,
+                teams: 0,
+                race: false,
+                coop: None,
             },
             auto_start: true,
             players: (1, 0),
+            bot_tier: BotTier::default(),
             human_first: true,
+            profiles: vec![],
             outcome: None,
+            best_of: 1,
+            bindings: Bindings::default(),
+            skip_animations: false,
+            motion: Motion::default(),
+            theme: String::new(),
+            pack: None,
+            color_blind: None,
+            seed: None,
+        }
+    }
+}
+/// Options given at launch, like on the command line, that are not to be saved
+/// till changed in the game. Must be used as a resource
+#[derive(Debug, Clone, Resource)]
+pub struct Overrides {
+    /// Options as they were saved
+    pub saved: MemoryGOpts,
+    /// Options the game was launched with
+    pub given: MemoryGOpts,
+}
+impl Overrides {
+    /// Options to save, the saved ones where the game still has the ones given
+    pub fn restore(&self, opts: &MemoryGOpts) -> MemoryGOpts {
+        fn unless_changed<T: PartialEq + Copy>(value: &mut T, given: T, saved: T) {
+            if *value == given {
+                *value = saved;
+            }
+        }
+        let (given, saved) = (&self.given, &self.saved);
+        let mut opts = opts.clone();
+        unless_changed(&mut opts.level, given.level, saved.level);
+        unless_changed(&mut opts.mode.rule, given.mode.rule, saved.mode.rule);
+        unless_changed(&mut opts.mode.combo, given.mode.combo, saved.mode.combo);
+        unless_changed(&mut opts.mode.duel, given.mode.duel, saved.mode.duel);
+        unless_changed(&mut opts.mode.full_plate, given.mode.full_plate, saved.mode.full_plate);
+        unless_changed(&mut opts.mode.race, given.mode.race, saved.mode.race);
+        unless_changed(&mut opts.players, given.players, saved.players);
+        unless_changed(&mut opts.human_first, given.human_first, saved.human_first);
+        unless_changed(&mut opts.auto_start, given.auto_start, saved.auto_start);
+        opts
+    }
+}
+/// Save the options whenever what is saved of them changes
+pub fn save_options(
+    opts: Res<MemoryGOpts>,
+    overrides: Option<Res<Overrides>>,
+    client: Option<Res<NetClient>>,
+    store: Res<Store>,
+    mut saved: Local<Option<String>>,
+) {
+    if !opts.is_changed() {
+        return;
+    }
+    let mut opts = overrides.map_or_else(|| opts.clone(), |overrides| overrides.restore(&opts));
+    // Clients play the mode and level of the host, keeping their own
+    let last = saved.as_deref().and_then(|data| ron::from_str::<MemoryGOpts>(data).ok());
+    if let Some(last) = last.filter(|_| client.is_some()) {
+        opts.mode = last.mode;
+        opts.level = last.level;
+    }
+    let Ok(data) = ron::to_string(&opts) else { return };
+    // The options the game starts with are saved already
+    if saved.is_some() && saved.as_ref() != Some(&data) {
+        store.save("options.ron", &opts);
+    }
+    *saved = Some(data);
+}
+/// Game speed, and whether the cards are to move at all
+#[cfg_attr(feature = "dev", derive(bevy_inspector_egui::Inspectable))]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Motion {
+    /// Multiplier of the game speed, higher is faster
+    #[cfg_attr(feature = "dev", inspectable(min = 0.25, max = 4.))]
+    pub speed: f32,
+    /// No shakes or bounces, and the cards flip at once
+    pub reduced: bool,
+}
+impl Default for Motion {
+    fn default() -> Self {
+        Self {
+            speed: 1.,
+            reduced: false,
+        }
+    }
+}
+impl Motion {
+    /// Speeds to pick from the menu
+    pub const SPEEDS: [f32; 5] = [0.5, 1., 1.5, 2., 3.];
+    /// Duration of a wait or a fade at the game speed
+    pub fn pace(&self, duration: Duration) -> Duration {
+        // Tweens need some time to run at all
+        duration
+            .div_f32(self.speed.max(0.25))
+            .max(Duration::from_millis(1))
+    }
+    /// Duration of the cards moving, instant when the motion is reduced
+    pub fn moves(&self, duration: Duration) -> Duration {
+        match self.reduced {
+            true => Duration::from_millis(1),
+            false => self.pace(duration),
+        }
+    }
+}
+/// Options to start from, switched from the menu
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Preset {
+    Kids,
+    Party,
+    Hard,
+}
+impl Preset {
+    pub const ALL: [Preset; 3] = [Preset::Kids, Preset::Party, Preset::Hard];
+    pub fn desc(&self) -> &str {
+        match self {
+            Preset::Kids => "Small board of any colored pairs, cleared together",
+            Preset::Party => "Two teams of two, streaks score more",
+            Preset::Hard => "Largest board of checkered decks against a bot",
         }
     }
+    /// Set the game options of the preset, keeping the seated profiles and auto start
+    pub fn apply(&self, opts: &mut MemoryGOpts) {
+        let default = Mode::default();
+        let (level, mode, players) = match self {
+            Preset::Kids => (
+                0,
+                Mode {
+                    rule: AnyColor,
+                    scoring: ScoringPolicy::Classic,
+                    coop: Some(Budget::Turns),
+                    ..default
+                },
+                (2, 0),
+            ),
+            Preset::Party => (
+                2,
+                Mode {
+                    scoring: ScoringPolicy::Streak,
+                    teams: 2,
+                    ..default
+                },
+                (4, 0),
+            ),
+            Preset::Hard => (
+                5,
+                Mode {
+                    rule: CheckeredDeck,
+                    scoring: ScoringPolicy::Penalty,
+                    combo: false,
+                    ..default
+                },
+                (1, 1),
+            ),
+        };
+        opts.level = level;
+        opts.mode = mode;
+        opts.players = players;
+        // Without bots the first seat is a human's
+        opts.human_first |= players.1 == 0;
+        opts.best_of = 1;
+    }
+    /// Preset matching the options
+    pub fn of(opts: &MemoryGOpts) -> Option<Self> {
+        Self::ALL.into_iter().find(|preset| {
+            let mut preset_opts = opts.clone();
+            preset.apply(&mut preset_opts);
+            preset_opts.level == opts.level
+                && preset_opts.mode == opts.mode
+                && preset_opts.players == opts.players
+                && preset_opts.best_of == opts.best_of
+        })
+    }
 }
+
 impl MemoryGOpts {
     pub fn deck_params(&self) -> (u8, u8) {
         let (deck_size, ct_jump): (u8, u8) = match self.mode.rule {
@@ -56,38 +277,132 @@ impl MemoryGOpts {
         )
     }
     pub fn outcome(&self) -> String {
-        match self.outcome {
-            Some(p) => format!(
-                "You {}",
-                if self.human_first && p == 0 {
-                    "Won"
-                } else {
-                    "Lost"
-                }
+        match &self.outcome {
+            Some(outcome) if outcome.stars() == Some(0) => "Out of Budget, Try Again".to_string(),
+            Some(outcome) if outcome.stars().is_some() => {
+                format!("Cleared Together! Stars: {}/3", outcome.stars().unwrap())
+            }
+            Some(outcome) if outcome.is_draw() => "Draw".to_string(),
+            Some(outcome) if outcome.iter().filter(|s| !s.bot).count() == 1 => {
+                let human = outcome.iter().find(|s| !s.bot).unwrap();
+                format!(
+                    "You {}",
+                    match human.place {
+                        1 if outcome.is_shared() => "Shared the Win",
+                        1 => "Won",
+                        _ => "Lost",
+                    }
+                )
+            }
+            Some(outcome) if !outcome.winning_teams().is_empty() => format!(
+                "{} {}",
+                outcome
+                    .winning_teams()
+                    .iter()
+                    .map(|&t| format!("Team {}", team_name(t)))
+                    .collect::<Vec<_>>()
+                    .join(" & "),
+                if outcome.is_shared() { "Share the Win" } else { "Won" }
+            ),
+            Some(outcome) => format!(
+                "{} {}",
+                outcome
+                    .winners()
+                    .map(|s| s.name.clone())
+                    .collect::<Vec<_>>()
+                    .join(" & "),
+                if outcome.is_shared() { "Share the Win" } else { "Won" }
             ),
             None => "None".to_string(),
         }
     }
-    pub fn create_players(&self) -> Vec<Player> {
-        let mut weights = [self.players.0, self.players.1];
+    /// Profiles for each of the seated players, a profile seated twice only takes the first seat
+    pub fn seats(&self, players: &[Player], profiles: &Profiles) -> Seats {
+        let mut humans = self.profiles.iter().enumerate();
+        let first = |i: usize, name: &String| !self.profiles[..i].contains(name);
+        Seats(
+            players
+                .iter()
+                .map(|pl| match pl.is_bot() {
+                    true => None,
+                    false => humans
+                        .next()
+                        .filter(|&(i, name)| first(i, name))
+                        .and_then(|(_, name)| profiles.get(name).cloned()),
+                })
+                .collect(),
+        )
+    }
+    /// Seat the profile for the nth human, `None` for the default name, with its preferred
+    /// settings. A profile seated for another human is not seated again
+    pub fn seat_profile(&mut self, seat: usize, profile: Option<&Profile>) -> bool {
+        let name = profile.map_or(String::new(), |p| p.name.clone());
+        let taken = |(i, x): (usize, &String)| i != seat && !name.is_empty() && *x == name;
+        if self.profiles.iter().enumerate().any(taken) {
+            return false;
+        }
+        if self.profiles.len() <= seat {
+            self.profiles.resize(seat + 1, String::new());
+        }
+        self.profiles[seat] = name;
+        if let Some(profile) = profile {
+            self.level = profile.level.unwrap_or(self.level);
+            self.mode = profile.mode.unwrap_or(self.mode);
+        }
+        true
+    }
+    /// Seat the local humans, bots and remote humans
+    pub fn create_players(&self, remotes: u8) -> Vec<Player> {
+        let remotes = remotes.min(MAX_SEATS.saturating_sub(self.players.0 + self.players.1));
+        let mut weights = [self.players.0, self.players.1, remotes];
         let mut players = vec![];
         let mut rng = thread_rng();
         let mut idx = 0u8;
         while !weights.iter().all(|&x| x == 0) {
             let dist = WeightedIndex::new(&weights).unwrap();
-            let choice = if idx == 0 {
-                !self.human_first as usize
+            let first = !self.human_first as usize;
+            let choice = if idx == 0 && weights[first] > 0 {
+                first
             } else {
                 dist.sample(&mut rng)
             };
             weights[choice] -= 1;
-            players.push(if choice == 1 {
-                Player::Bolts(Bolts(idx, 0))
-            } else {
-                Player::Flesh(Flesh(idx, 0))
+            players.push(match choice {
+                0 => Player::Flesh(Flesh(idx, 0)),
+                1 => Player::Bolts(Bolts(idx, 0)),
+                _ => Player::Wire(Wire(idx, 0)),
             });
             idx += 1;
         }
         players
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn presets_without_bots_seat_a_human_first() {
+        for preset in [Preset::Kids, Preset::Party] {
+            let mut opts = MemoryGOpts {
+                human_first: false,
+                ..default()
+            };
+            preset.apply(&mut opts);
+            assert!(opts.human_first);
+            assert!(matches!(opts.create_players(0)[0], Player::Flesh(_)));
+        }
+    }
+    #[test]
+    fn the_first_seat_goes_to_whoever_is_playing() {
+        let opts = MemoryGOpts {
+            players: (0, 2),
+            human_first: true,
+            ..default()
+        };
+        let players = opts.create_players(0);
+        assert_eq!(players.len(), 2);
+        assert!(players.iter().all(|pl| pl.is_bot()));
+    }
+}
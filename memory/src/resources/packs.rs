@@ -0,0 +1,229 @@
+use {
+    super::{RuleSet, Store},
+    bevy::{
+        prelude::*,
+        render::render_resource::{Extent3d, TextureDimension},
+    },
+    serde::{Deserialize, Serialize},
+    std::ops::Deref,
+};
+
+/// Where the images of a card pack are, relative to the assets folder
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PackImages {
+    /// An image file for each face, in the folder
+    Folder { dir: String, files: Vec<String> },
+    /// Equal tiles of one image, counted row by row from the top left
+    Atlas {
+        image: String,
+        columns: u32,
+        rows: u32,
+    },
+}
+/// What of a card tells the image on its face
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FaceKey {
+    /// Rank of the card, its suits share the image
+    Rank,
+    /// Rank and suit, one of the 56 faces of a deck
+    Face,
+}
+/// Card faces drawn from images instead of the card font
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CardPack {
+    pub name: String,
+    pub images: PackImages,
+    pub key: FaceKey,
+    /// Image of each key, the images in order if not given. The keys wrap around the images
+    #[serde(default)]
+    pub faces: Vec<usize>,
+}
+impl CardPack {
+    /// Index of the image on the face of the card, out of `len` images
+    pub fn image_of(&self, card: u8, len: usize) -> usize {
+        let key = match self.key {
+            FaceKey::Rank => card % 14,
+            FaceKey::Face => card % 56,
+        } as usize;
+        self.faces.get(key).copied().unwrap_or(key) % len.max(1)
+    }
+    /// Whether the faces tell apart the cards the rule pairs, only the ranks with any color
+    pub fn tells(&self, rule: RuleSet) -> bool {
+        self.key == FaceKey::Face || rule == RuleSet::AnyColor
+    }
+    /// Why the pack has no images to show, if it has none
+    fn check(&self) -> Result<(), String> {
+        match &self.images {
+            PackImages::Folder { files, .. } if files.is_empty() => Err("no files".to_string()),
+            PackImages::Atlas { columns, rows, .. } if columns * rows == 0 => {
+                Err(format!("an atlas of {columns} columns and {rows} rows"))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Card packs of the player, read from `packs.ron`. Must be used as a resource
+#[derive(Debug, Clone, Resource)]
+pub struct CardPacks(pub Vec<CardPack>);
+impl FromWorld for CardPacks {
+    fn from_world(world: &mut World) -> Self {
+        let store = world.get_resource_or_insert_with(Store::default);
+        let packs: Vec<CardPack> = store.load("packs.ron").unwrap_or_default();
+        Self(
+            packs
+                .into_iter()
+                .filter(|pack| {
+                    pack.check()
+                        .map_err(|e| warn!("Could not use the {} pack, of {e}", pack.name))
+                        .is_ok()
+                })
+                .collect(),
+        )
+    }
+}
+impl Deref for CardPacks {
+    type Target = Vec<CardPack>;
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+impl CardPacks {
+    pub fn get(&self, name: &str) -> Option<&CardPack> {
+        self.iter().find(|pack| pack.name == name)
+    }
+}
+
+/// Images of the pack picked for the game, no pack for the card font. Must be used as a resource
+#[derive(Debug, Default, Resource)]
+pub struct PackFaces {
+    pack: Option<CardPack>,
+    /// Atlas waiting to be loaded, to be cut into the images of its columns and rows
+    atlas: Option<(Handle<Image>, u32, u32)>,
+    images: Vec<Handle<Image>>,
+}
+impl PackFaces {
+    /// Load the images of the pack, unless it is the one loaded
+    pub fn load(
+        &mut self,
+        pack: Option<&CardPack>,
+        asset_server: &AssetServer,
+        images: &mut Assets<Image>,
+    ) {
+        if self.pack.as_ref() == pack {
+            return;
+        }
+        self.pack = pack.cloned();
+        self.atlas = None;
+        self.images = match pack.map(|pack| &pack.images) {
+            None => vec![],
+            Some(PackImages::Folder { dir, files }) => files
+                .iter()
+                .map(|file| asset_server.load(format!("{dir}/{file}").as_str()))
+                .collect(),
+            Some(PackImages::Atlas {
+                image,
+                columns,
+                rows,
+            }) => {
+                self.atlas = Some((asset_server.load(image.as_str()), *columns, *rows));
+                // Tiles are filled in once the atlas is loaded
+                (0..columns * rows)
+                    .map(|_| images.add(Image::default()))
+                    .collect()
+            }
+        };
+    }
+    /// Image on the face of the card, none for the card font
+    pub fn face(&self, card: u8) -> Option<Handle<Image>> {
+        let pack = self.pack.as_ref()?;
+        self.images
+            .get(pack.image_of(card, self.images.len()))
+            .cloned()
+    }
+    pub fn spawn_face(&self, card: u8, size: f32) -> Option<ImageBundle> {
+        Some(ImageBundle {
+            style: Style {
+                size: Size::new(Val::Px(size), Val::Px(size)),
+                ..Default::default()
+            },
+            image: self.face(card)?.into(),
+            visibility: Visibility { is_visible: false },
+            ..Default::default()
+        })
+    }
+    /// Cut the atlas into the images, once it is loaded
+    pub fn slice(&mut self, images: &mut Assets<Image>) {
+        let Some((atlas, columns, rows)) = &self.atlas else { return };
+        let (columns, rows) = (*columns, *rows);
+        let Some(atlas) = images.get(atlas) else { return };
+        let size = atlas.texture_descriptor.size;
+        let (width, height) = (size.width / columns, size.height / rows);
+        let pixel = atlas.data.len() / (size.width * size.height) as usize;
+        let tiles: Vec<Image> = (0..rows * columns)
+            .map(|i| {
+                let (x, y) = (i % columns * width, i / columns * height);
+                let data = (y..y + height)
+                    .flat_map(|row| {
+                        let start = (row * size.width + x) as usize * pixel;
+                        atlas.data[start..start + width as usize * pixel]
+                            .iter()
+                            .copied()
+                    })
+                    .collect();
+                Image::new(
+                    Extent3d {
+                        width,
+                        height,
+                        depth_or_array_layers: 1,
+                    },
+                    TextureDimension::D2,
+                    data,
+                    atlas.texture_descriptor.format,
+                )
+            })
+            .collect();
+        for (handle, tile) in self.images.iter().zip(tiles) {
+            images.set_untracked(handle, tile);
+        }
+        self.atlas = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::MemoryStorage};
+
+    #[test]
+    fn packs_with_no_images_are_dropped() {
+        let mut world = World::new();
+        let store = Store(Box::new(MemoryStorage::default()));
+        let packs = r#"[
+            (name: "Flags", images: Atlas(image: "flags.png", columns: 8, rows: 7), key: Face),
+            (name: "Empty", images: Atlas(image: "empty.png", columns: 0, rows: 7), key: Face),
+            (name: "Animals", images: Folder(dir: "animals", files: []), key: Rank),
+        ]"#;
+        store.0.write("packs.ron", packs).unwrap();
+        world.insert_resource(store);
+        let packs = CardPacks::from_world(&mut world);
+        let names: Vec<&str> = packs.iter().map(|pack| pack.name.as_str()).collect();
+        assert_eq!(names, ["Flags"]);
+    }
+    #[test]
+    fn packs_of_ranks_tell_only_any_color() {
+        let pack = |key| CardPack {
+            name: "Animals".to_string(),
+            images: PackImages::Folder {
+                dir: "animals".to_string(),
+                files: vec!["cat.png".to_string()],
+            },
+            key,
+            faces: vec![],
+        };
+        for rule in [RuleSet::SameColor, RuleSet::Zebra, RuleSet::TwoDecks] {
+            assert!(!pack(FaceKey::Rank).tells(rule));
+            assert!(pack(FaceKey::Face).tells(rule));
+        }
+        assert!(pack(FaceKey::Rank).tells(RuleSet::AnyColor));
+    }
+}
@@ -0,0 +1,78 @@
+use {
+    crate::{input::Control, Deck},
+    bevy::prelude::*,
+    std::ops::{Deref, DerefMut},
+};
+
+/// Gamepad of a local human, with the card in focus of its player
+#[derive(Debug, Copy, Clone)]
+pub struct Pad {
+    pub gamepad: Gamepad,
+    /// Kept while the others take their turns, to pick up from on the next turn
+    pub idx: usize,
+    /// Direction the stick is pushed in, to move only once per push
+    pub stick: Option<Control>,
+}
+
+/// Gamepads bound to the seats of the local humans, in the order they connected.
+/// Must be used as a resource
+#[derive(Debug, Default, Resource)]
+pub struct Pads(Vec<Option<Pad>>);
+impl Pads {
+    /// Unbind the disconnected gamepads, and bind the new ones to the first free seats
+    pub fn sync(&mut self, gamepads: &Gamepads) {
+        for pad in self.0.iter_mut() {
+            if pad.map_or(false, |pad| !gamepads.contains(pad.gamepad)) {
+                *pad = None;
+            }
+        }
+        let mut new: Vec<Gamepad> = gamepads
+            .iter()
+            .filter(|&gamepad| !self.iter().flatten().any(|pad| pad.gamepad == gamepad))
+            .collect();
+        new.sort_by_key(|gamepad| gamepad.id);
+        for gamepad in new {
+            let pad = Some(Pad {
+                gamepad,
+                idx: 0,
+                stick: None,
+            });
+            match self.0.iter_mut().find(|pad| pad.is_none()) {
+                Some(free) => *free = pad,
+                None => self.0.push(pad),
+            }
+        }
+    }
+    /// Gamepad bound to the seat on the board
+    pub fn of(&mut self, deck: &Deck, seat: u8, local: Option<u8>) -> Option<&mut Pad> {
+        self.0
+            .iter_mut()
+            .enumerate()
+            .find(|(nth, _)| Self::seat(deck, *nth, local) == Some(seat))
+            .and_then(|(_, pad)| pad.as_mut())
+    }
+    /// Seat of the nth gamepad on the board, among the local humans
+    pub fn seat(deck: &Deck, nth: usize, local: Option<u8>) -> Option<u8> {
+        match local {
+            Some(seat) => (nth == 0).then_some(seat),
+            None => deck
+                .seats()
+                .iter()
+                .filter(|pl| !pl.is_bot() && !pl.is_remote())
+                .nth(nth)
+                .map(|pl| pl.id()),
+        }
+    }
+}
+impl Deref for Pads {
+    type Target = Vec<Option<Pad>>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+impl DerefMut for Pads {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
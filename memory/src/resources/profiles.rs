@@ -0,0 +1,126 @@
+use {
+    super::{Mode, Store},
+    bevy::prelude::*,
+    serde::{Deserialize, Serialize},
+    std::ops::Deref,
+};
+
+/// Named player, persisted on disk
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Profile {
+    pub name: String,
+    /// Highlight color for the player, also used to tint their pairs
+    pub color: Color,
+    /// Avatar image, relative to the assets folder
+    #[serde(default)]
+    pub avatar: Option<String>,
+    /// Level applied when the profile is seated
+    #[serde(default)]
+    pub level: Option<u8>,
+    /// Mode applied when the profile is seated
+    #[serde(default)]
+    pub mode: Option<Mode>,
+}
+impl Profile {
+    /// Colors to pick for a profile from the menu
+    pub const PALETTE: [Color; 8] = [
+        Color::rgb(0.878, 0.067, 0.373),
+        Color::rgb(0., 0.659, 0.42),
+        Color::rgb(1., 0.749, 0.),
+        Color::rgb(0.059, 0.322, 0.729),
+        Color::rgb(0.6, 0.4, 0.8),
+        Color::rgb(1., 0.5, 0.31),
+        Color::rgb(0.25, 0.88, 0.82),
+        Color::rgb(0.55, 0.55, 0.55),
+    ];
+    pub fn new(name: &str, color: Color) -> Self {
+        Self {
+            name: name.to_string(),
+            color,
+            avatar: None,
+            level: None,
+            mode: None,
+        }
+    }
+    /// Color of the palette after the one of the profile
+    pub fn next_color(&mut self) {
+        let i = Self::PALETTE.iter().position(|&c| c == self.color);
+        self.color = Self::PALETTE[i.map_or(0, |i| (i + 1) % Self::PALETTE.len())];
+    }
+}
+
+/// Profiles available for the seats, written to disk only once saved from the menu.
+/// Must be used as a resource
+#[derive(Debug, Clone, Resource, Serialize, Deserialize)]
+pub struct Profiles(pub Vec<Profile>);
+impl FromWorld for Profiles {
+    fn from_world(world: &mut World) -> Self {
+        let store = world.get_resource_or_insert_with(Store::default);
+        Self::load(&store).unwrap_or_else(|| {
+            Self(
+                ["Ruby", "Jade", "Amber", "Sapphire"]
+                    .into_iter()
+                    .zip(Profile::PALETTE)
+                    .map(|(name, color)| Profile::new(name, color))
+                    .collect(),
+            )
+        })
+    }
+}
+impl Deref for Profiles {
+    type Target = Vec<Profile>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+impl Profiles {
+    pub fn get(&self, name: &str) -> Option<&Profile> {
+        self.iter().find(|p| p.name == name)
+    }
+    /// Add a profile with a name and color not taken yet, returning its index
+    pub fn create(&mut self) -> usize {
+        let name = (1..)
+            .map(|i| format!("Player {i}"))
+            .find(|name| self.get(name).is_none())
+            .unwrap();
+        let color = Profile::PALETTE
+            .into_iter()
+            .find(|&c| self.iter().all(|p| p.color != c))
+            .unwrap_or(Profile::PALETTE[self.len() % Profile::PALETTE.len()]);
+        self.0.push(Profile::new(&name, color));
+        self.len() - 1
+    }
+    pub fn load(store: &Store) -> Option<Self> {
+        store.load("profiles.ron")
+    }
+    pub fn save(&self, store: &Store) {
+        store.save("profiles.ron", self)
+    }
+}
+
+/// Profile of each seat on a board, indexed by seat. Seats without one use defaults.
+/// Must be used as a component on the `Board`
+#[derive(Debug, Clone, Default, Component)]
+pub struct Seats(pub Vec<Option<Profile>>);
+impl Deref for Seats {
+    type Target = Vec<Option<Profile>>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+impl Seats {
+    pub fn name(&self, seat: u8, bot: bool) -> String {
+        match self.get(seat as usize) {
+            Some(Some(profile)) => profile.name.clone(),
+            _ => format!("{} {seat}", if bot { "Bot" } else { "Human" }),
+        }
+    }
+    /// Color to tint revealed pairs with, only for seats with a profile
+    pub fn tint(&self, seat: u8) -> Option<Color> {
+        self.get(seat as usize)
+            .and_then(|p| p.as_ref())
+            .map(|p| p.color)
+    }
+}
@@ -0,0 +1,174 @@
+use {
+    super::{BotTier, Outcome, RuleSet, Seats, Store},
+    bevy::prelude::*,
+    serde::{Deserialize, Serialize},
+};
+
+/// Rating every profile and bot starts from
+pub const INITIAL_RATING: i32 = 1200;
+/// Most a rating can change by in one game
+const K: f32 = 32.;
+
+/// Rating of a profile, or of a bot tier, for a rule set and level
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rating {
+    pub name: String,
+    pub rule: RuleSet,
+    /// Level of the board, which sets its size
+    pub level: u8,
+    pub rating: i32,
+    pub games: u32,
+}
+
+/// Ratings of the profiles and the bot tiers, persisted on disk. Must be used as a resource
+#[derive(Debug, Clone, Resource)]
+pub struct Ratings {
+    ratings: Vec<Rating>,
+    /// Rating change of each player in the last game
+    changes: Vec<(String, i32)>,
+}
+impl FromWorld for Ratings {
+    fn from_world(world: &mut World) -> Self {
+        let store = world.get_resource_or_insert_with(Store::default);
+        Self {
+            ratings: store.load("ratings.ron").unwrap_or_default(),
+            changes: vec![],
+        }
+    }
+}
+impl Ratings {
+    pub fn save(&self, store: &Store) {
+        store.save("ratings.ron", &self.ratings)
+    }
+    pub fn get(&self, name: &str, rule: RuleSet, level: u8) -> i32 {
+        self.ratings
+            .iter()
+            .find(|r| r.name == name && r.rule == rule && r.level == level)
+            .map_or(INITIAL_RATING, |r| r.rating)
+    }
+    /// Ratings for the rule set and level, best first
+    pub fn table(&self, rule: RuleSet, level: u8) -> Vec<&Rating> {
+        let mut table: Vec<&Rating> = self
+            .ratings
+            .iter()
+            .filter(|r| r.rule == rule && r.level == level)
+            .collect();
+        table.sort_by_key(|r| -r.rating);
+        table
+    }
+    pub fn changes(&self) -> &[(String, i32)] {
+        &self.changes
+    }
+    /// Name a bot of the tier is rated under
+    pub fn bot(tier: BotTier) -> String {
+        format!("Bot ({tier:?})")
+    }
+    /// Rate every pair of players by their placement, where the bots are rated by their tier.
+    /// Humans without a profile are not rated, but still count as opponents.
+    /// A profile or tier on several seats is rated, and met, once by its best placed seat
+    pub fn rate(
+        &mut self,
+        outcome: &Outcome,
+        seats: &Seats,
+        tier: BotTier,
+        rule: RuleSet,
+        level: u8,
+    ) {
+        self.changes.clear();
+        if outcome.stars().is_some() || outcome.len() < 2 {
+            return;
+        }
+        let names: Vec<Option<String>> = outcome
+            .iter()
+            .map(|s| match seats.get(s.seat as usize) {
+                Some(Some(profile)) => Some(profile.name.clone()),
+                _ if s.bot => Some(Self::bot(tier)),
+                _ => None,
+            })
+            .collect();
+        let ratings: Vec<f32> = names
+            .iter()
+            .map(|name| name.as_ref().map_or(INITIAL_RATING, |n| self.get(n, rule, level)) as f32)
+            .collect();
+        // Standings are ordered by placement, the best placed seat is the first
+        let first = |j: usize| names[j].is_none() || !names[..j].contains(&names[j]);
+        for (i, standing) in outcome.iter().enumerate() {
+            let Some(name) = &names[i] else { continue };
+            if !first(i) {
+                continue;
+            }
+            let others: Vec<usize> = (0..outcome.len())
+                .filter(|&j| names[j] != names[i] && first(j))
+                .collect();
+            if others.is_empty() {
+                continue;
+            }
+            let delta: f32 = others
+                .iter()
+                .map(|&j| {
+                    let other = &outcome[j];
+                    let actual = match standing.place.cmp(&other.place) {
+                        std::cmp::Ordering::Less => 1.,
+                        std::cmp::Ordering::Equal => 0.5,
+                        std::cmp::Ordering::Greater => 0.,
+                    };
+                    let expected = 1. / (1. + 10f32.powf((ratings[j] - ratings[i]) / 400.));
+                    actual - expected
+                })
+                .sum();
+            let delta = (K * delta / others.len() as f32).round() as i32;
+            match self
+                .ratings
+                .iter_mut()
+                .find(|r| &r.name == name && r.rule == rule && r.level == level)
+            {
+                Some(r) => {
+                    r.rating += delta;
+                    r.games += 1;
+                }
+                None => self.ratings.push(Rating {
+                    name: name.clone(),
+                    rule,
+                    level,
+                    rating: INITIAL_RATING + delta,
+                    games: 1,
+                }),
+            }
+            self.changes.push((standing.name.clone(), delta));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            components::{Bolts, Flesh, Player},
+            Profile,
+        },
+    };
+
+    #[test]
+    fn seats_of_one_profile_or_tier_are_rated_once() {
+        let players: Vec<Player> = vec![
+            Flesh(0, 0).into(),
+            Bolts(1, 0).into(),
+            Flesh(2, 0).into(),
+            Bolts(3, 0).into(),
+        ];
+        let ruby = Some(Profile::new("Ruby", Color::RED));
+        let seats = Seats(vec![ruby.clone(), None, ruby, None]);
+        let outcome = Outcome::new(&players, &[3, 2, 1, 0], 0).with_names(&seats);
+        let mut ratings = Ratings {
+            ratings: vec![],
+            changes: vec![],
+        };
+        ratings.rate(&outcome, &seats, BotTier::Sharp, RuleSet::Zebra, 0);
+        let bot = Ratings::bot(BotTier::Sharp);
+        // Each is rated by its best seat, against the best seat of the other
+        assert_eq!(ratings.changes(), [("Ruby".to_string(), 16), ("Bot 1".to_string(), -16)]);
+        assert_eq!(ratings.get(&bot, RuleSet::Zebra, 0), INITIAL_RATING - 16);
+        assert_eq!(ratings.table(RuleSet::Zebra, 0).len(), 2);
+    }
+}
@@ -0,0 +1,108 @@
+use {
+    super::{Outcome, Seats},
+    crate::components::Player,
+    bevy::prelude::*,
+};
+
+/// Rematch of the last game, dealt the same cards
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum Rematch {
+    /// Everyone keeps their seat
+    SameSeed,
+    /// Everyone moves a seat over
+    SwapSeats,
+}
+
+/// Games played since the last new game, kept between the boards. Must be used as a resource
+#[derive(Debug, Clone, Default, Resource)]
+pub struct Series {
+    /// Entrants of the series with their profiles, in the seats of the first game
+    players: Vec<Player>,
+    seats: Seats,
+    /// Entrant on each seat
+    order: Vec<u8>,
+    /// Games won by each entrant
+    wins: Vec<u8>,
+    games: u8,
+    seed: u64,
+    /// Rematch to deal for the next game, otherwise new cards are dealt
+    pub rematch: Option<Rematch>,
+}
+impl Series {
+    /// Start over with a new series on the next game
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+    /// Seating and seed of the next game, with the first turn rotating between the seats.
+    /// A new series is seated by `fresh`, and a decided one is started over.
+    /// New cards are dealt from the `seed` if given
+    pub fn deal(
+        &mut self,
+        best_of: u8,
+        seed: Option<u64>,
+        fresh: impl FnOnce() -> (Vec<Player>, Seats),
+    ) -> (Vec<Player>, Seats, u64, u8) {
+        if self.players.is_empty() {
+            let (players, seats) = fresh();
+            *self = Self {
+                order: (0..players.len() as u8).collect(),
+                wins: vec![0; players.len()],
+                players,
+                seats,
+                rematch: self.rematch,
+                ..default()
+            };
+        } else if self.winner(best_of).is_some() {
+            self.wins.iter_mut().for_each(|x| *x = 0);
+            self.games = 0;
+        }
+        match self.rematch.take() {
+            Some(Rematch::SwapSeats) => self.order.rotate_left(1),
+            Some(Rematch::SameSeed) => (),
+            None => self.seed = seed.unwrap_or_else(rand::random),
+        }
+        let players = self
+            .order
+            .iter()
+            .enumerate()
+            .map(|(seat, &e)| self.players[e as usize].seated(seat as u8))
+            .collect();
+        let seats = Seats(
+            self.order
+                .iter()
+                .map(|&e| self.seats[e as usize].clone())
+                .collect(),
+        );
+        let first = self.games % self.order.len().max(1) as u8;
+        (players, seats, self.seed, first)
+    }
+    /// Count the game towards the series, a shared win counts for no one
+    pub fn record(&mut self, outcome: &Outcome) {
+        self.games += 1;
+        if outcome.is_shared() || outcome.stars().is_some() {
+            return;
+        }
+        for standing in outcome.winners() {
+            if let Some(&e) = self.order.get(standing.seat as usize) {
+                self.wins[e as usize] += 1;
+            }
+        }
+    }
+    /// Entrant who won the majority of the series
+    pub fn winner(&self, best_of: u8) -> Option<u8> {
+        (0..self.wins.len() as u8).find(|&e| self.wins[e as usize] > best_of / 2)
+    }
+    pub fn name(&self, entrant: u8) -> String {
+        self.seats.name(entrant, self.players[entrant as usize].is_bot())
+    }
+    /// Wins of each entrant so far
+    pub fn standings(&self) -> String {
+        (0..self.wins.len() as u8)
+            .map(|e| format!("{}: {}", self.name(e), self.wins[e as usize]))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+    pub fn games(&self) -> u8 {
+        self.games
+    }
+}
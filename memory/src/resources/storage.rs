@@ -0,0 +1,186 @@
+//! Storage of the saved files, like options, profiles and ratings, as ron
+use {
+    bevy::prelude::*,
+    serde::{de::DeserializeOwned, Serialize},
+    std::{collections::HashMap, sync::Mutex},
+};
+
+/// Backend keeping the saved files
+pub trait Storage: Send + Sync {
+    fn read(&self, file: &str) -> Option<String>;
+    fn write(&self, file: &str, data: &str) -> Result<(), String>;
+}
+
+/// Files in the config folder of the user
+#[cfg(not(target_arch = "wasm32"))]
+pub struct FileStorage(pub std::path::PathBuf);
+#[cfg(not(target_arch = "wasm32"))]
+impl Default for FileStorage {
+    fn default() -> Self {
+        Self(
+            std::env::var_os("HOME")
+                .map(|home| {
+                    std::path::PathBuf::from(home)
+                        .join(".config")
+                        .join("recall-stones")
+                })
+                .unwrap_or_default(),
+        )
+    }
+}
+#[cfg(not(target_arch = "wasm32"))]
+impl Storage for FileStorage {
+    fn read(&self, file: &str) -> Option<String> {
+        std::fs::read_to_string(self.0.join(file)).ok()
+    }
+    fn write(&self, file: &str, data: &str) -> Result<(), String> {
+        std::fs::create_dir_all(&self.0).map_err(|e| e.to_string())?;
+        std::fs::write(self.0.join(file), data).map_err(|e| e.to_string())
+    }
+}
+
+/// Files kept in memory till exit, for tests and where there is no file system
+#[derive(Default)]
+pub struct MemoryStorage(Mutex<HashMap<String, String>>);
+impl Storage for MemoryStorage {
+    fn read(&self, file: &str) -> Option<String> {
+        self.0.lock().unwrap().get(file).cloned()
+    }
+    fn write(&self, file: &str, data: &str) -> Result<(), String> {
+        self.0
+            .lock()
+            .unwrap()
+            .insert(file.to_string(), data.to_string());
+        Ok(())
+    }
+}
+
+/// Storage used for the saved files. Must be used as a resource,
+/// inserted before the plugin to change the backend
+#[derive(Resource)]
+pub struct Store(pub Box<dyn Storage>);
+impl Default for Store {
+    #[cfg(not(target_arch = "wasm32"))]
+    fn default() -> Self {
+        Self(Box::new(FileStorage::default()))
+    }
+    #[cfg(target_arch = "wasm32")]
+    fn default() -> Self {
+        Self(Box::new(MemoryStorage::default()))
+    }
+}
+impl Store {
+    pub fn load<T: DeserializeOwned>(&self, file: &str) -> Option<T> {
+        let data = self.0.read(file)?;
+        ron::from_str(&data)
+            .map_err(|e| warn!("Could not read {file}: {e}"))
+            .ok()
+    }
+    pub fn save<T: Serialize>(&self, file: &str, value: &T) {
+        let saved = ron::ser::to_string_pretty(value, Default::default())
+            .map_err(|e| e.to_string())
+            .and_then(|data| self.0.write(file, &data));
+        if let Err(e) = saved {
+            warn!("Could not save {file}: {e}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{net::NetClient, save_options, MemoryGOpts, Overrides},
+        std::net::TcpListener,
+    };
+
+    fn store() -> Store {
+        Store(Box::new(MemoryStorage::default()))
+    }
+
+    #[test]
+    fn saved_files_load_back() {
+        let store = store();
+        let opts = MemoryGOpts {
+            level: 3,
+            ..default()
+        };
+        store.save("options.ron", &opts);
+        assert_eq!(store.load::<MemoryGOpts>("options.ron"), Some(opts));
+    }
+
+    #[test]
+    fn missing_or_malformed_files_load_nothing() {
+        let store = store();
+        assert_eq!(store.load::<MemoryGOpts>("options.ron"), None);
+        store.0.write("options.ron", "(level: ").unwrap();
+        assert_eq!(store.load::<MemoryGOpts>("options.ron"), None);
+    }
+
+    #[test]
+    fn options_are_saved_once_they_change() {
+        let mut app = App::new();
+        app.insert_resource(store())
+            .init_resource::<MemoryGOpts>()
+            .add_system(save_options);
+        app.update();
+        assert_eq!(app.world.resource::<Store>().0.read("options.ron"), None);
+        app.world.resource_mut::<MemoryGOpts>().level = 4;
+        app.update();
+        let saved = app
+            .world
+            .resource::<Store>()
+            .load::<MemoryGOpts>("options.ron");
+        assert_eq!(saved.map(|opts| opts.level), Some(4));
+    }
+
+    #[test]
+    fn options_given_at_launch_are_saved_only_once_changed() {
+        let saved = MemoryGOpts::default();
+        let given = MemoryGOpts {
+            level: 5,
+            ..default()
+        };
+        let mut app = App::new();
+        app.insert_resource(store())
+            .insert_resource(given.clone())
+            .insert_resource(Overrides { saved, given })
+            .add_system(save_options);
+        app.update();
+        app.world.resource_mut::<MemoryGOpts>().players.1 = 2;
+        app.update();
+        let saved = app
+            .world
+            .resource::<Store>()
+            .load::<MemoryGOpts>("options.ron")
+            .unwrap();
+        assert_eq!((saved.level, saved.players.1), (0, 2));
+    }
+    #[test]
+    fn options_of_the_host_are_not_saved_by_its_clients() {
+        let host = TcpListener::bind("127.0.0.1:0").unwrap();
+        let client = NetClient::connect(host.local_addr().unwrap()).unwrap();
+        let mut app = App::new();
+        app.insert_resource(store())
+            .insert_resource(client)
+            .init_resource::<MemoryGOpts>()
+            .add_system(save_options);
+        app.update();
+        {
+            let mut opts = app.world.resource_mut::<MemoryGOpts>();
+            opts.level = 5;
+            opts.mode.duel = true;
+            opts.players.1 = 2;
+        }
+        app.update();
+        let saved = app
+            .world
+            .resource::<Store>()
+            .load::<MemoryGOpts>("options.ron")
+            .unwrap();
+        assert_eq!(
+            (saved.level, saved.mode.duel, saved.players.1),
+            (0, false, 2)
+        );
+    }
+}
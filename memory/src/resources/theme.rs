@@ -0,0 +1,196 @@
+use {
+    super::Store,
+    bevy::prelude::*,
+    serde::{Deserialize, Serialize},
+    std::ops::Deref,
+};
+
+/// Themes bundled with the game, the first is the default
+const BUNDLED: [&str; 3] = [
+    include_str!("../../../assets/themes/light.ron"),
+    include_str!("../../../assets/themes/dark.ron"),
+    include_str!("../../../assets/themes/high-contrast.ron"),
+];
+
+/// Colors and fonts of the board, read from RON or JSON
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Theme {
+    pub name: String,
+    pub background: Color,
+    /// Slot of each card on the board
+    pub board: Color,
+    /// Back and face of the cards of the two decks
+    pub cards: [(Color, Color); 2],
+    /// Ink of the two suit colors of the faces
+    pub suits: [Color; 2],
+    /// Highlight of the current player without a profile
+    pub highlight: Color,
+    /// Score panel of the players waiting for their turn
+    pub panel: Color,
+    /// Color of the number of flips of a card, from once on, the last for any more
+    pub counts: Vec<Color>,
+    /// Fonts, relative to the assets folder
+    pub score_font: String,
+    pub card_font: String,
+}
+impl Theme {
+    /// Theme of a RON or JSON file
+    pub fn parse(data: &str) -> Result<Self, String> {
+        ron::from_str::<Self>(data)
+            .map_err(|e| e.to_string())
+            .or_else(|e| serde_json::from_str(data).map_err(|_| e))
+            .map(Self::distinct)
+    }
+    /// Cards are told face up from face down by their color, so no two card colors may be equal
+    fn distinct(mut self) -> Self {
+        for i in 1..4 {
+            let mut color = self.card(i);
+            while (0..i).any(|j| self.card(j) == color) {
+                let [r, g, b, a] = color.as_rgba_f32();
+                let nudge = if r < 0.5 { 1. / 255. } else { -1. / 255. };
+                color = Color::rgba(r + nudge, g, b, a);
+            }
+            *self.card_mut(i) = color;
+        }
+        self
+    }
+    fn card(&self, i: usize) -> Color {
+        let pair = self.cards[i / 2];
+        if i % 2 == 0 {
+            pair.0
+        } else {
+            pair.1
+        }
+    }
+    fn card_mut(&mut self, i: usize) -> &mut Color {
+        let pair = &mut self.cards[i / 2];
+        if i % 2 == 0 {
+            &mut pair.0
+        } else {
+            &mut pair.1
+        }
+    }
+}
+
+/// Palettes of colors told apart with a color blindness, of the Okabe-Ito colors
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ColorBlind {
+    /// Red and green look alike, the most common
+    Deuteranopia,
+    /// Red and green look alike, and reds are dark
+    Protanopia,
+    /// Blue and yellow look alike
+    Tritanopia,
+}
+impl ColorBlind {
+    pub const ALL: [ColorBlind; 3] = [
+        ColorBlind::Deuteranopia,
+        ColorBlind::Protanopia,
+        ColorBlind::Tritanopia,
+    ];
+}
+impl Theme {
+    /// The theme with the suits, card backs, highlight and flip counts in the palette.
+    /// The highlight is none of the other colors, so a found pair is not taken for the focus
+    pub fn with_palette(mut self, palette: Option<ColorBlind>) -> Self {
+        let rgb = Color::rgb_u8;
+        let (black, white) = (rgb(0, 0, 0), rgb(255, 255, 255));
+        let (orange, sky, green, yellow) = (
+            rgb(230, 159, 0),
+            rgb(86, 180, 233),
+            rgb(0, 158, 115),
+            rgb(240, 228, 66),
+        );
+        let (blue, vermillion, purple) = (rgb(0, 114, 178), rgb(213, 94, 0), rgb(204, 121, 167));
+        let (suit, backs, highlight, counts) = match palette {
+            None => return self,
+            Some(ColorBlind::Deuteranopia) => (
+                blue,
+                [orange, blue],
+                purple,
+                [sky, white, yellow, orange, vermillion],
+            ),
+            Some(ColorBlind::Protanopia) => (
+                blue,
+                [orange, blue],
+                green,
+                [sky, white, yellow, orange, purple],
+            ),
+            Some(ColorBlind::Tritanopia) => (
+                vermillion,
+                [vermillion, green],
+                yellow,
+                [green, white, purple, vermillion, black],
+            ),
+        };
+        self.suits[1] = suit;
+        self.cards[0].0 = backs[0];
+        self.cards[1].0 = backs[1];
+        self.highlight = highlight;
+        self.counts = counts.to_vec();
+        self.distinct()
+    }
+}
+
+/// Bundled themes, followed by those of the player in `themes.ron` or `themes.json`.
+/// Must be used as a resource
+#[derive(Debug, Clone, Resource)]
+pub struct Themes(Vec<Theme>);
+impl FromWorld for Themes {
+    fn from_world(world: &mut World) -> Self {
+        let store = world.get_resource_or_insert_with(Store::default);
+        let mut themes: Vec<Theme> = BUNDLED
+            .iter()
+            .map(|data| Theme::parse(data).unwrap())
+            .collect();
+        let custom = ["themes.ron", "themes.json"].into_iter().find_map(|file| {
+            let data = store.0.read(file)?;
+            ron::from_str::<Vec<Theme>>(&data)
+                .map_err(|e| e.to_string())
+                .or_else(|e| serde_json::from_str(&data).map_err(|_| e))
+                .map_err(|e| warn!("Could not read {file}: {e}"))
+                .ok()
+        });
+        for theme in custom.into_iter().flatten().map(Theme::distinct) {
+            // Custom themes replace the bundled ones of the same name
+            match themes.iter_mut().find(|t| t.name == theme.name) {
+                Some(bundled) => *bundled = theme,
+                None => themes.push(theme),
+            }
+        }
+        Self(themes)
+    }
+}
+impl Deref for Themes {
+    type Target = Vec<Theme>;
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+impl Themes {
+    /// Theme of the name, or the default one
+    pub fn get(&self, name: &str) -> &Theme {
+        self.iter().find(|t| t.name == name).unwrap_or(&self.0[0])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn palettes_highlight_in_a_color_of_its_own() {
+        for data in BUNDLED {
+            for palette in ColorBlind::ALL {
+                let theme = Theme::parse(data).unwrap().with_palette(Some(palette));
+                let backs = theme.cards.map(|(back, _)| back);
+                let others: Vec<&Color> = theme.counts.iter().chain(&backs).collect();
+                assert!(
+                    !others.contains(&&theme.highlight),
+                    "{palette:?} of {}",
+                    theme.name
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,215 @@
+//! Read-only stream of a game for spectators, as newline delimited json.
+//!
+//! Only what is face up on the board is sent: backs of the cards when dealt,
+//! and a face once that card is flipped.
+use {
+    crate::{components::Player, events::*, net::Writer, Deck, Mode, Outcome},
+    bevy::prelude::*,
+    serde::Serialize,
+    std::{
+        net::{TcpListener, ToSocketAddrs},
+        sync::{Arc, Mutex},
+    },
+};
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum SpectatorEvent {
+    Deal {
+        mode: Mode,
+        /// Backs of the cards, telling apart only the checkered ones
+        cards: Vec<u8>,
+        players: Vec<Player>,
+    },
+    Flip {
+        idx: usize,
+        card: u8,
+        player: u8,
+    },
+    Match {
+        cards: [usize; 2],
+        player: u8,
+    },
+    Mismatch {
+        cards: [usize; 2],
+        player: u8,
+    },
+    /// Turn over, with the cards flipped in it face down again unless paired.
+    /// Also sent back to the same player, when they take back a card
+    Turn {
+        player: u8,
+        scores: Vec<i16>,
+    },
+    Result {
+        standings: Outcome,
+    },
+}
+
+/// Event with the board it happened on, as there may be several
+#[derive(Debug, Serialize)]
+struct BoardEvent {
+    board: u32,
+    #[serde(flatten)]
+    event: SpectatorEvent,
+}
+
+#[derive(Default)]
+struct Stream {
+    /// Events of the current game, replayed to spectators joining late
+    history: Vec<String>,
+    spectators: Vec<Writer>,
+}
+
+/// Spectators watching the game. Must be used as a resource
+#[derive(Resource, Clone)]
+pub struct Spectators(Arc<Mutex<Stream>>);
+impl Spectators {
+    pub fn bind(addr: impl ToSocketAddrs) -> std::io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        info!("Streaming to spectators on {:?}", listener.local_addr());
+        let stream = Arc::new(Mutex::new(Stream::default()));
+        let joined = stream.clone();
+        std::thread::spawn(move || {
+            for spectator in listener.incoming().flatten() {
+                let spectator = Writer::spawn(spectator);
+                let mut stream = joined.lock().unwrap();
+                if stream
+                    .history
+                    .iter()
+                    .all(|line| spectator.send(line.clone()))
+                {
+                    stream.spectators.push(spectator);
+                }
+            }
+        });
+        Ok(Self(stream))
+    }
+    /// Send the event of the board, tagged with its id
+    pub fn send(&self, board: Entity, event: SpectatorEvent) {
+        let event = BoardEvent {
+            board: board.index(),
+            event,
+        };
+        let mut line = match serde_json::to_string(&event) {
+            Ok(line) => line,
+            Err(e) => {
+                warn!("Could not stream {event:?}: {e}");
+                return;
+            }
+        };
+        line.push('\n');
+        let mut stream = self.0.lock().unwrap();
+        stream
+            .spectators
+            .retain(|spectator| spectator.send(line.clone()));
+        stream.history.push(line);
+    }
+    /// Forget the games played, for the spectators joining the next one
+    fn clear(&self) {
+        self.0.lock().unwrap().history.clear();
+    }
+}
+
+/// Stream the deal and the events of the games to the spectators
+#[allow(clippy::too_many_arguments)]
+pub fn stream(
+    spectators: Option<Res<Spectators>>,
+    dealt: Query<(Entity, &Deck), Added<Deck>>,
+    decks: Query<&Deck>,
+    mut flips: EventReader<CardFlipped>,
+    mut matches: EventReader<PairMatched>,
+    mut mismatches: EventReader<Mismatch>,
+    mut turns: EventReader<TurnChanged>,
+    mut finished: EventReader<GameFinished>,
+) {
+    let Some(spectators) = spectators else { return };
+    let scores = |board| decks.get(board).map_or(vec![], |deck| deck.scores.clone());
+    // The boards of a game are dealt together, as the boards of the last one are gone
+    if !dealt.is_empty() {
+        spectators.clear();
+    }
+    for (board, deck) in dealt.iter() {
+        spectators.send(
+            board,
+            SpectatorEvent::Deal {
+                mode: deck.mode(),
+                cards: deck.masked_cards(),
+                players: deck.seats().to_vec(),
+            },
+        );
+        spectators.send(
+            board,
+            SpectatorEvent::Turn {
+                player: deck.player(),
+                scores: deck.scores.clone(),
+            },
+        );
+    }
+    for flip in flips.iter() {
+        spectators.send(
+            flip.board,
+            SpectatorEvent::Flip {
+                idx: flip.idx,
+                card: flip.card,
+                player: flip.player,
+            },
+        );
+    }
+    for pair in matches.iter() {
+        spectators.send(
+            pair.board,
+            SpectatorEvent::Match {
+                cards: pair.cards,
+                player: pair.player,
+            },
+        );
+    }
+    for mismatch in mismatches.iter() {
+        spectators.send(
+            mismatch.board,
+            SpectatorEvent::Mismatch {
+                cards: mismatch.cards,
+                player: mismatch.player,
+            },
+        );
+    }
+    for turn in turns.iter() {
+        spectators.send(
+            turn.board,
+            SpectatorEvent::Turn {
+                player: turn.to,
+                scores: scores(turn.board),
+            },
+        );
+    }
+    for GameFinished { board, outcome } in finished.iter() {
+        spectators.send(
+            *board,
+            SpectatorEvent::Result {
+                standings: outcome.clone(),
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::components::Flesh};
+
+    #[test]
+    fn late_spectators_are_sent_only_the_game_in_play() {
+        let spectators = Spectators(default());
+        let mut app = App::new();
+        add_events(&mut app);
+        app.insert_resource(spectators.clone()).add_system(stream);
+        let seats: Vec<Player> = (0..2).map(|s| Flesh(s, 0).into()).collect();
+        let deal = || Deck::masked(Mode::default(), &[0, 14, 1, 15], &seats);
+        let board = app.world.spawn(deal()).id();
+        app.update();
+        app.world.despawn(board);
+        app.world.spawn(deal());
+        app.update();
+        // The deal and the first turn of the second game
+        assert_eq!(spectators.0.lock().unwrap().history.len(), 2);
+    }
+}
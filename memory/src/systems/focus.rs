@@ -0,0 +1,144 @@
+use {
+    crate::{
+        components::*,
+        events::TurnChanged,
+        input::{Action, PlayerAction},
+        net::NetClient,
+        Deck, GameState, MemoryGAssts, Pads, Seats, Series,
+    },
+    bevy::prelude::*,
+};
+
+/// Move the cursor of the player on their turn, or of a player waiting for it on their gamepad.
+/// The cursor picks up where the player left it on their last turn
+pub fn cursor(
+    mut boards: Query<(&Grid, &Deck, &mut Cursor)>,
+    mut pads: ResMut<Pads>,
+    mut actions: EventReader<PlayerAction>,
+    mut turns: EventReader<TurnChanged>,
+    client: Option<Res<NetClient>>,
+) {
+    let local = client.map(|client| client.seat);
+    for act in actions.iter() {
+        let Action::MoveCursor(dir) = act.action else { continue };
+        let Ok((grid, deck, mut cursor)) = boards.get_mut(act.board) else { continue };
+        if deck.player() == act.seat {
+            cursor.idx = grid.step(cursor.idx, dir);
+        } else if let Some(pad) = pads.of(deck, act.seat, local) {
+            pad.idx = grid.step(pad.idx.min(deck.len() - 1), dir);
+        }
+    }
+    for turn in turns.iter() {
+        let Ok((_, deck, mut cursor)) = boards.get_mut(turn.board) else { continue };
+        if let Some(pad) = pads.of(deck, turn.to, local) {
+            cursor.idx = pad.idx.min(deck.len() - 1);
+        }
+    }
+    for (_, deck, cursor) in boards.iter() {
+        if let Some(pad) = pads.of(deck, deck.player(), local) {
+            pad.idx = cursor.idx;
+        }
+    }
+}
+/// Ring the card in focus with the color of the current player, and raise it to be told by
+/// its size too
+pub fn focus_ring(
+    boards: Query<(Entity, &Deck, &Seats, &Cursor, ChangeTrackers<Cursor>)>,
+    cards: Query<(&Idx, &Parent, &InBoard)>,
+    mut slots: Query<(&mut BackgroundColor, &mut Transform), Without<Idx>>,
+    mut turns: EventReader<TurnChanged>,
+    assets: Res<MemoryGAssts>,
+) {
+    let turned: Vec<Entity> = turns.iter().map(|x| x.board).collect();
+    for (board, deck, seats, cursor, tracker) in boards.iter() {
+        if !tracker.is_changed() && !turned.contains(&board) {
+            continue;
+        }
+        for (id, parent, _) in cards.iter().filter(|x| x.2 .0 == board) {
+            let focused = cursor.shown && cursor.idx == id.0;
+            let color = if focused {
+                seats.tint(deck.player()).unwrap_or(assets.theme.highlight)
+            } else if deck.is_revealed(id.0) {
+                match seats.tint(deck.get_owner(id.0) - 1) {
+                    Some(tint) => tint,
+                    None => assets.board.color,
+                }
+            } else {
+                assets.board.color
+            };
+            if let Ok((mut slot, mut transform)) = slots.get_mut(parent.get()) {
+                slot.0 = color;
+                transform.scale = Vec3::splat(if focused { 1.12 } else { 1. });
+            }
+        }
+    }
+}
+/// Pause the game, or start a new one
+pub fn shortcuts(
+    mut actions: EventReader<PlayerAction>,
+    mut state: ResMut<State<GameState>>,
+    mut series: ResMut<Series>,
+) {
+    for act in actions.iter() {
+        match act.action {
+            Action::Pause => {
+                state.push(GameState::Menu).ok();
+            }
+            Action::NewGame => {
+                series.reset();
+                state.restart().ok();
+            }
+            _ => (),
+        }
+    }
+}
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{MemoryGOpts, MemoryStorage, Mode, Profile, RuleSet::AnyColor, Store, Themes},
+    };
+
+    #[test]
+    fn claimed_pairs_keep_the_color_of_their_owner_out_of_focus() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(AssetPlugin::default())
+            .add_asset::<Image>()
+            .add_event::<TurnChanged>()
+            .insert_resource(Store(Box::new(MemoryStorage::default())))
+            .init_resource::<MemoryGOpts>()
+            .init_resource::<Themes>()
+            .init_resource::<MemoryGAssts>()
+            .add_system(focus_ring);
+        let mode = Mode {
+            rule: AnyColor,
+            ..default()
+        };
+        let players: Vec<Player> = (0..2).map(|s| Flesh(s, 0).into()).collect();
+        let mut deck = Deck::masked(mode, &[0, 14, 1, 15], &players);
+        [0, 1].into_iter().for_each(|mv| deck.play(mv));
+        let seats = Seats(vec![
+            Some(Profile::new("Ruby", Color::RED)),
+            Some(Profile::new("Sky", Color::BLUE)),
+        ]);
+        let cursor = Cursor {
+            idx: 0,
+            shown: true,
+        };
+        let board = app.world.spawn((deck, seats, cursor)).id();
+        let slots: Vec<Entity> = (0..4)
+            .map(|idx| {
+                let slot = (BackgroundColor::default(), Transform::default());
+                let card = app.world.spawn((Idx(idx, 0), InBoard(board))).id();
+                app.world.spawn(slot).add_child(card).id()
+            })
+            .collect();
+        for idx in 0..3 {
+            app.world.get_mut::<Cursor>(board).unwrap().idx = idx;
+            app.update();
+            let color = |slot| app.world.get::<BackgroundColor>(slot).unwrap().0;
+            assert_eq!((color(slots[0]), color(slots[1])), (Color::RED, Color::RED));
+        }
+    }
+}
@@ -1,3 +1,4 @@
-pub use {turn::*, uncover::*};
+pub use {focus::*, turn::*, uncover::*};
+mod focus;
 mod turn;
 mod uncover;
@@ -1,85 +1,193 @@
 use super::ROT_TIME;
 use crate::tween::BeTween;
 use bevy_tweening::{Animator, EaseFunction, Tween};
-use std::time::Duration;
+use std::{collections::HashMap, time::Duration};
 use {
-    crate::{components::*, Deck},
+    crate::{
+        components::*,
+        events::*,
+        input::{Action, PlayerAction},
+        net::{Msg, NetClient, NetHost},
+        Budget, Deck, MemoryGAssts, MemoryGOpts, Seats,
+    },
     bevy::prelude::*,
-    rand::seq::IteratorRandom,
 };
 
-/// Whether the ai or human, get the index of the move and add `Open` Component to that entity
+/// Whether the ai or human, get the index of the move and play it on the `Deck` of each board.
+/// The humans here play through their actions, and may take back the first card of their turn.
+/// No moves are played on a board while its cards turn, the last card picked waits for them
+#[allow(clippy::too_many_arguments)]
 pub fn turn(
-    mut players: Query<&mut Player>,
+    mut boards: Query<(Entity, &mut Deck)>,
+    players: Query<(&Player, &InBoard)>,
     time: Res<Time>,
-    mut timer: Local<Timer>,
-    mut deck: ResMut<Deck>,
-    mut cards: Query<(&mut Idx, &Interaction, ChangeTrackers<Interaction>)>,
+    opts: Res<MemoryGOpts>,
+    mut timers: Local<HashMap<Entity, Timer>>,
+    mut queued: Local<HashMap<Entity, usize>>,
+    mut cards: Query<(&mut Idx, &InBoard)>,
+    animating: Query<&InBoard, With<Animating>>,
+    mut actions: EventReader<PlayerAction>,
+    mut host: Option<ResMut<NetHost>>,
+    client: Option<Res<NetClient>>,
+    mut moves: Moves,
 ) {
-    if timer.duration() == Duration::ZERO {
-        *timer = Timer::new(Duration::from_millis(1729), TimerMode::Repeating);
+    // Moves of a client are played by its host
+    if client.is_some() {
+        return;
     }
-    let mut player = players
-        .iter_mut()
-        .find(|pl| deck.player() == pl.deref().0)
-        .unwrap();
+    let actions: Vec<PlayerAction> = actions.iter().copied().collect();
+    timers.retain(|&board, _| boards.contains(board));
+    queued.retain(|&board, _| boards.contains(board));
+    for (board, mut deck) in boards.iter_mut() {
+        let timer = timers.entry(board).or_insert_with(|| {
+            let wait = opts.motion.pace(Duration::from_millis(1729));
+            Timer::new(wait, TimerMode::Repeating)
+        });
+        let (player, _) = players
+            .iter()
+            .find(|(pl, b)| b.0 == board && deck.player() == pl.id())
+            .unwrap();
+        let mut host = host.as_mut().filter(|host| host.board() == Some(board));
+        // Seats of the disconnected clients are taken over by bots
+        let is_bot = player.is_bot()
+            || player.is_remote() && !host.as_ref().map_or(false, |h| h.connected(player.id()));
+        let mut acts: Vec<Action> = actions
+            .iter()
+            .filter(|act| act.board == board && act.seat == deck.player())
+            .map(|act| act.action)
+            .collect();
+        if animating.iter().any(|b| b.0 == board) {
+            let picked = acts.iter().rev().find_map(|act| match *act {
+                Action::FlipCard(idx) => Some(idx),
+                _ => None,
+            });
+            if let Some(idx) = picked.filter(|_| !is_bot && !player.is_remote()) {
+                queued.insert(board, idx);
+            }
+            continue;
+        }
+        if let Some(idx) = queued.remove(&board) {
+            acts.push(Action::FlipCard(idx));
+        }
+        let remote = host.as_mut().and_then(|host| host.take_move(deck.player()));
+        let mut cards = cards.iter_mut().filter(|(_, b)| b.0 == board);
 
-    if let Some(mut id) = if player.is_bot() && timer.tick(time.delta()).just_finished() {
-        timer.reset();
-        cards
-            .iter_mut()
-            .filter(|(id, _, _)| deck.is_available_move(id.0))
-            .choose(&mut rand::thread_rng())
-            .map(|x| x.0)
-    } else if player.is_bot() {
-        None
-    } else {
-        cards
-            .iter_mut()
-            .find(|(id, &flip, tracker)| {
-                tracker.is_changed() && flip == Interaction::Clicked && deck.is_available_move(id.0)
+        // Clients are not told of a card taken back
+        if !is_bot && !player.is_remote() && host.is_none() && acts.contains(&Action::Undo) {
+            moves.undo(board, &mut deck);
+            continue;
+        }
+        if let Some(mut id) = if is_bot && timer.tick(time.delta()).just_finished() {
+            timer.reset();
+            deck.bot_move(opts.bot_tier, &mut rand::thread_rng())
+                .and_then(|mv| cards.find(|(id, _)| id.0 == mv))
+                .map(|x| x.0)
+        } else if is_bot {
+            None
+        } else if player.is_remote() {
+            remote.and_then(|mv| {
+                cards
+                    .find(|(id, _)| id.0 == mv && deck.is_available_move(mv))
+                    .map(|x| x.0)
             })
-            .map(|x| x.0)
-    } {
-        deck.play(id.0);
-        if deck.opened.len() == 2 {
-            player.inc_turn();
+        } else {
+            acts.iter()
+                .find_map(|act| match *act {
+                    Action::FlipCard(idx) if deck.is_available_move(idx) => Some(idx),
+                    _ => None,
+                })
+                .and_then(|idx| cards.find(|(id, _)| id.0 == idx))
+                .map(|x| x.0)
+        } {
+            moves.play(board, &mut deck, id.0);
+            if let Some(host) = &host {
+                host.broadcast(&Msg::Flip {
+                    idx: id.0,
+                    card: deck.get_card(id.0),
+                });
+            }
+            id.1 += 1;
+        };
+    }
+}
+/// Run the clock of each board, updating the time left of the co-op games against it
+pub fn clock(
+    mut boards: Query<(Entity, &mut Deck)>,
+    mut teams: Query<(&TeamScore, &InBoard, &mut Text)>,
+    time: Res<Time>,
+    mut moves: Moves,
+) {
+    for (board, mut deck) in boards.iter_mut() {
+        let left = deck.budget_left();
+        moves.tick(board, &mut deck, time.delta());
+        if deck.mode().coop == Some(Budget::Time) && deck.budget_left() != left {
+            for (team, _, mut text) in teams.iter_mut().filter(|x| x.1 .0 == board) {
+                text.sections[0].value = team_text(&deck, team.0);
+            }
         }
-        id.1 += 1;
-    };
+    }
+}
+/// Text of a team score, or the shared score and the budget left in co-op
+pub(crate) fn team_text(deck: &Deck, team: u8) -> String {
+    let score = deck.team_scores()[team as usize];
+    match (deck.mode().coop, deck.budget_left()) {
+        (Some(budget), Some(left)) => format!(
+            "Together: {score}, {left} {} left",
+            match budget {
+                Budget::Turns => "Turns",
+                Budget::Time => "Seconds",
+            }
+        ),
+        _ => format!("Team {}: {score}", team_name(team)),
+    }
 }
+#[allow(clippy::type_complexity)]
 pub fn score_board(
-    players: Query<(Entity, &Player, &Parent)>,
-    deck: Res<Deck>,
-    mut text: Query<&mut Text>,
+    players: Query<(Entity, &Player, &Parent, &InBoard)>,
+    decks: Query<(Entity, &Deck, &Seats, ChangeTrackers<Deck>)>,
+    mut text: Query<&mut Text, Without<TeamScore>>,
+    mut teams: Query<(&TeamScore, &InBoard, &mut Text)>,
+    mut flips: EventReader<CardFlipped>,
+    mut turns: EventReader<TurnChanged>,
+    opts: Res<MemoryGOpts>,
+    assets: Res<MemoryGAssts>,
     mut cmd: Commands,
 ) {
-    if deck.is_changed() {
-        for (entity, player, parent) in players.iter() {
-            let is_player = player.id() == deck.player();
-            cmd.entity(**parent).insert(Animator::new(Tween::new(
-                EaseFunction::QuadraticIn,
-                ROT_TIME * 2,
-                BeTween::with_lerp(move |c: &mut BackgroundColor, _, r| {
-                    let end = if is_player {
-                        Color::GREEN
-                    } else {
-                        Color::WHITE
-                    };
-                    let start: Vec4 = c.0.into();
-                    *c = BackgroundColor(start.lerp(end.into(), r).into());
-                }),
-            )));
+    let flipped: Vec<Entity> = flips.iter().map(|x| x.board).collect();
+    let turned: Vec<Entity> = turns.iter().map(|x| x.board).collect();
+    for (board, deck, seats, tracker) in decks.iter() {
+        let turn_changed = tracker.is_added() || turned.contains(&board);
+        if !turn_changed && !flipped.contains(&board) {
+            continue;
+        }
+        for (team, _, mut text) in teams.iter_mut().filter(|x| x.1 .0 == board) {
+            text.sections[0].value = team_text(deck, team.0);
+        }
+        for (entity, player, parent, _) in players.iter().filter(|x| x.3 .0 == board) {
+            if turn_changed {
+                let is_player = player.id() == deck.player();
+                let highlight = seats.tint(player.id()).unwrap_or(assets.theme.highlight);
+                let panel = assets.theme.panel;
+                cmd.entity(**parent).insert(Animator::new(Tween::new(
+                    EaseFunction::QuadraticIn,
+                    opts.motion.pace(ROT_TIME * 2),
+                    BeTween::with_lerp(move |c: &mut BackgroundColor, _, r| {
+                        let end = if is_player { highlight } else { panel };
+                        let start: Vec4 = c.0.into();
+                        *c = BackgroundColor(start.lerp(end.into(), r).into());
+                    }),
+                )));
+            }
             let mut text = text.get_mut(entity).unwrap();
+            // The player on their turn is marked, not only highlighted
+            let marker = if player.id() == deck.player() { "> " } else { "" };
             text.sections[0].value = format!(
-                "{} {}\nScore: {}\nOpened: {}\nTurns: {}\n",
-                if player.is_bot() { "Bot" } else { "Human" },
-                player.deref().0,
+                "{marker}{}\nScore ({:?}): {}\nOpened: {}\nTurns: {}\n",
+                seats.name(player.id(), player.is_bot()),
+                deck.mode().scoring,
                 deck.scores[player.deref().0 as usize],
-                deck.iter()
-                    .filter(|&&c| c / 128 == 1 + player.deref().0 as u16)
-                    .count(),
-                player.deref().1
+                deck.opened_by(player.deref().0),
+                deck.seats()[player.id() as usize].deref().1
             );
         }
     }
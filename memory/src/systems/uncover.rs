@@ -1,5 +1,11 @@
-use crate::{components::*, tween::*, Deck, MemoryGAssts, MemoryGOpts};
-use {bevy::prelude::*, std::time::Duration};
+use crate::{
+    components::*, events::*, tween::*, Board, Deck, MemoryGAssts, MemoryGOpts, Outcome, PackFaces,
+    Ratings, Seats, Series, Store, Themes,
+};
+use {
+    bevy::prelude::*,
+    std::{collections::HashMap, time::Duration},
+};
 
 pub(crate) const ROT_TIME: Duration = Duration::from_millis(81);
 pub fn card_flip(
@@ -13,24 +19,113 @@ pub fn card_flip(
         }
     });
 }
+/// Cut the atlas of the card pack into the faces, once it is loaded
+pub fn slice_atlas(mut faces: ResMut<PackFaces>, mut images: ResMut<Assets<Image>>) {
+    faces.slice(&mut images);
+}
+/// Switch to the theme picked in the options, recoloring the boards in play by the role of
+/// each color, as themes may share colors between roles
+#[allow(clippy::too_many_arguments)]
+pub fn retheme(
+    opts: Res<MemoryGOpts>,
+    themes: Res<Themes>,
+    mut assets: ResMut<MemoryGAssts>,
+    asset_server: Res<AssetServer>,
+    mut boards: Query<(Entity, &Deck, &mut Cursor), With<Board>>,
+    cards: Query<(Entity, &Idx, &InBoard)>,
+    children: Query<&Children>,
+    mut colors: Query<&mut BackgroundColor>,
+    mut texts: Query<&mut Text>,
+    mut images: Query<&mut UiImage>,
+) {
+    if !opts.is_changed() {
+        return;
+    }
+    let theme = themes.get(&opts.theme).clone();
+    if theme.clone().with_palette(opts.color_blind) == assets.theme
+        && opts.color_blind == assets.color_blind
+    {
+        return;
+    }
+    let pattern = assets.pattern.clone();
+    let themed = MemoryGAssts::themed(theme, opts.color_blind, pattern, &asset_server);
+    let old = std::mem::replace(&mut *assets, themed);
+    for (board, _, mut cursor) in boards.iter_mut() {
+        let halves = children.get(board).into_iter().flat_map(|c| c.iter().copied());
+        for node in std::iter::once(board).chain(halves) {
+            if let Ok(mut color) = colors.get_mut(node) {
+                color.0 = assets.theme.background;
+            }
+        }
+        // The slots of the cards are colored again by the focus ring
+        cursor.set_changed();
+    }
+    for (card, id, board) in cards.iter() {
+        let Ok((_, deck, _)) = boards.get(board.0) else { continue };
+        let val = deck.get_card(id.0);
+        let nth = (val > 55) as usize;
+        let (back, face) = &assets.card[nth];
+        // The decks keep their patterns, of the material of their color
+        if let Ok(mut color) = colors.get_mut(card) {
+            let face_up = color.0 == old.card[nth].1.color;
+            color.0 = if face_up { face.color } else { back.color };
+        }
+        if let Ok(mut image) = images.get_mut(card) {
+            image.0 = back.texture.clone();
+        }
+        // Face of the card, with the flips of a found pair under it
+        for &face in children.get(card).into_iter().flat_map(|c| c.iter()) {
+            if let Ok(mut text) = texts.get_mut(face) {
+                *text = assets.card_text(val, text.sections[0].style.font_size);
+            }
+            for &flips in children.get(face).into_iter().flat_map(|c| c.iter()) {
+                if let Ok(mut text) = texts.get_mut(flips) {
+                    text.sections[0].style.color = assets.count_color(id.1);
+                }
+            }
+        }
+    }
+}
+/// Turn the flipped cards, shake the mismatches and clear away the pairs
+#[allow(clippy::too_many_arguments)]
 pub fn uncover(
     mut cmd: Commands,
-    mut opened: Local<Vec<usize>>,
-    deck: Res<Deck>,
+    mut face_up: Local<HashMap<Entity, [usize; 2]>>,
     assets: Res<MemoryGAssts>,
-    cards: Query<(Entity, &Idx)>,
+    opts: Res<MemoryGOpts>,
+    seats: Query<&Seats>,
+    boards: Query<(), Added<Board>>,
+    cards: Query<(Entity, &Idx, &Parent, &InBoard)>,
     children: Query<&Children>,
+    mut flips: EventReader<CardFlipped>,
+    mut undos: EventReader<FlipUndone>,
+    mut matches: EventReader<PairMatched>,
+    mut mismatches: EventReader<Mismatch>,
+    mut finished: EventReader<GameFinished>,
 ) {
+    if !boards.is_empty() {
+        face_up.retain(|&board, _| seats.contains(board));
+    }
+    let gate = !opts.skip_animations;
+    let motion = opts.motion;
     let mut tween = |show, entity| {
-        cmd.entity(entity).insert(Animator::new(rot_seq(ROT_TIME)));
+        cmd.entity(entity)
+            .insert(Animator::new(rot_seq(motion.moves(ROT_TIME))));
+        if gate {
+            cmd.entity(entity).insert(Animating);
+        }
         for &child in &**children.get(entity).unwrap() {
             cmd.entity(child)
-                .insert(Animator::new(vis_seq(ROT_TIME, show)));
+                .insert(Animator::new(vis_seq(motion.moves(ROT_TIME), show)));
         }
     };
-    let find_card = |i| cards.iter().find(|(_, &id)| id.0 == i).unwrap();
-    let new_turn = deck.opened.len() == 1;
-    let deck_complete = deck.outcome().is_some();
+    let find_card = |board, i| {
+        cards
+            .iter()
+            .find(|(_, &id, _, b)| b.0 == board && id.0 == i)
+            .unwrap()
+    };
+    let completed: Vec<Entity> = finished.iter().map(|x| x.board).collect();
 
     let text = |id: &Idx| TextBundle {
         text: Text::from_section(
@@ -58,76 +153,122 @@ pub fn uncover(
         },
         ..default()
     };
-    if if deck_complete {
-        !opened.is_empty()
-    } else {
-        deck.opened.len() != opened.len()
-    } {
-        if new_turn {
-            opened.drain(..).for_each(|c| {
-                if !deck.is_revealed(c) {
-                    tween(false, find_card(c).0);
-                }
-            });
+    for flip in flips.iter() {
+        // Mismatched cards stay face up till the next flip
+        if let Some(pair) = face_up.remove(&flip.board) {
+            pair.iter()
+                .for_each(|&c| tween(false, find_card(flip.board, c).0));
         }
-        deck.opened.last().map(|&v| {
-            opened.push(v);
-            tween(true, find_card(v).0);
-            Some(())
-        });
-        if !new_turn {
-            cards
-                .iter()
-                .filter(|x| deck.opened.contains(&x.1 .0))
-                .for_each(|(entity, id)| {
-                    if deck.is_available_move(id.0) {
-                        // println!("SSS");
-                        cmd.entity(entity)
-                            .insert(Animator::new(shake_seq(ROT_TIME)));
-                    } else if deck_complete {
-                        opened.clear();
-                        if let Ok(children) = children.get(entity) {
-                            children.iter().for_each(|&child| {
-                                cmd.entity(child).with_children(|parent| {
-                                    parent.spawn(text(id));
-                                });
+        tween(true, find_card(flip.board, flip.idx).0);
+    }
+    for undo in undos.iter() {
+        tween(false, find_card(undo.board, undo.idx).0);
+    }
+    for mismatch in mismatches.iter() {
+        for &c in mismatch.cards.iter().filter(|_| !motion.reduced) {
+            let mut card = cmd.entity(find_card(mismatch.board, c).0);
+            card.insert(Animator::new(shake_seq(motion.pace(ROT_TIME))));
+            if gate {
+                card.insert(Animating);
+            }
+        }
+        face_up.insert(mismatch.board, mismatch.cards);
+    }
+    for pair in matches.iter() {
+        let tint = seats
+            .get(pair.board)
+            .ok()
+            .and_then(|seats| seats.tint(pair.player));
+        cards
+            .iter()
+            .filter(|x| x.3 .0 == pair.board && pair.cards.contains(&x.1 .0))
+            .for_each(|(entity, id, parent, _)| {
+                if let Some(tint) = tint {
+                    cmd.entity(parent.get()).insert(BackgroundColor(tint));
+                }
+                if completed.contains(&pair.board) {
+                    if let Ok(children) = children.get(entity) {
+                        children.iter().for_each(|&child| {
+                            cmd.entity(child).with_children(|parent| {
+                                parent.spawn(text(id));
+                            });
+                        });
+                    };
+                } else {
+                    cmd.entity(entity)
+                        .insert(Animator::new(vis_seq(motion.pace(12 * ROT_TIME), false)));
+                    for &child in &**children.get(entity).unwrap() {
+                        cmd.entity(child)
+                            .insert(Animator::new(vis_seq(motion.pace(9 * ROT_TIME), false)))
+                            .with_children(|parent| {
+                                parent.spawn(text(id)).insert(Animator::new(vis_seq(
+                                    motion.pace(8 * ROT_TIME),
+                                    false,
+                                )));
                             });
-                        };
-                    } else {
-                        cmd.entity(entity)
-                            .insert(Animator::new(vis_seq(12 * ROT_TIME, false)));
-                        for &child in &**children.get(entity).unwrap() {
-                            cmd.entity(child)
-                                .insert(Animator::new(vis_seq(9 * ROT_TIME, false)))
-                                .with_children(|parent| {
-                                    parent
-                                        .spawn(text(id))
-                                        .insert(Animator::new(vis_seq(8 * ROT_TIME, false)));
-                                });
-                        }
                     }
-                });
+                }
+            });
+    }
+}
+/// Let the moves on a board through once its cards stop turning
+pub fn settle(mut cmd: Commands, mut completed: EventReader<TweenCompleted>) {
+    for done in completed.iter().filter(|x| x.user_data == SETTLED) {
+        if let Some(mut card) = cmd.get_entity(done.entity) {
+            card.remove::<Animating>();
         }
     }
 }
 
+/// Reveal the cards of the finished boards, a race is over as soon as a board is cleared
 pub fn deck_complete(
     mut cmd: Commands,
-    cards: Query<Entity, With<Idx>>,
-    deck: Res<Deck>,
+    decks: Query<(Entity, &Deck, &Seats)>,
+    cards: Query<(Entity, &InBoard), With<Idx>>,
     children: Query<&Children>,
     mut opts: ResMut<MemoryGOpts>,
+    mut series: ResMut<Series>,
+    mut ratings: ResMut<Ratings>,
+    store: Res<Store>,
+    mut finished: EventReader<GameFinished>,
 ) {
-    opts.outcome = deck.outcome();
-    if opts.outcome.is_some() {
+    let mut ended = vec![];
+    for GameFinished { board, outcome } in finished.iter() {
+        let Ok((_, deck, seats)) = decks.get(*board) else { continue };
+        // Boards of a race end together
+        if ended.contains(board) {
+            continue;
+        }
+        if deck.mode().race {
+            let racing = || decks.iter().filter(|(_, deck, _)| deck.mode().race);
+            let racers: Vec<_> = racing()
+                .map(|(_, deck, _)| {
+                    let seat = deck.player() as usize;
+                    (deck.seats()[seat], deck.scores[seat], deck.completed())
+                })
+                .collect();
+            opts.outcome = Some(Outcome::race(&racers).with_names(seats));
+            ended.extend(racing().map(|(board, ..)| board));
+        } else {
+            opts.outcome = Some(outcome.clone().with_names(seats));
+            series.record(outcome);
+            ended.push(*board);
+        }
+        if let Some(outcome) = &opts.outcome {
+            ratings.rate(outcome, seats, opts.bot_tier, deck.mode().rule, opts.level);
+            ratings.save(&store);
+        }
+    }
+    for board in ended {
         let mut cycle = (15..27).cycle();
+        let motion = opts.motion;
         let mut tween = |e| {
             cmd.entity(e).insert(Animator::new(vis_seq(
-                cycle.next().unwrap() * ROT_TIME,
+                motion.pace(cycle.next().unwrap() * ROT_TIME),
                 true,
             )));
         };
-        for entity in cards.iter() {
+        for (entity, _) in cards.iter().filter(|x| x.1 .0 == board) {
             tween(entity);
             for &child in &**children.get(entity).unwrap() {
                 tween(child);
@@ -136,6 +277,44 @@ pub fn deck_complete(
                 };
             }
         }
-        cmd.remove_resource::<Deck>();
+        cmd.entity(board).remove::<Deck>();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{ColorBlind, MemoryStorage, Mode},
+    };
+
+    #[test]
+    fn color_blind_mode_redraws_the_cards_in_play() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(AssetPlugin::default())
+            .add_asset::<Image>()
+            .insert_resource(Store(Box::new(MemoryStorage::default())))
+            .init_resource::<MemoryGOpts>()
+            .init_resource::<Themes>()
+            .init_resource::<MemoryGAssts>()
+            .add_system(retheme);
+        let seats: Vec<Player> = (0..2).map(|s| Flesh(s, 0).into()).collect();
+        let deck = Deck::masked(Mode::default(), &[14, 0, 15, 1], &seats);
+        let assets = app.world.resource::<MemoryGAssts>().clone();
+        let board = app.world.spawn((Board, deck, Cursor::default())).id();
+        let face = app.world.spawn(assets.spawn_card(14, 27.)).id();
+        let back = BackgroundColor(assets.card[0].0.color);
+        let card = app.world.spawn((Idx(0, 0), InBoard(board), back)).add_child(face).id();
+        app.update();
+        assert_eq!(app.world.get::<Text>(face).unwrap().sections.len(), 1);
+
+        app.world.resource_mut::<MemoryGOpts>().color_blind = Some(ColorBlind::Deuteranopia);
+        app.update();
+        // The second suit color is dotted, and the card turns to the back of the palette
+        let back = app.world.resource::<MemoryGAssts>().card[0].0.color;
+        assert_eq!(app.world.get::<Text>(face).unwrap().sections.len(), 2);
+        assert_eq!(app.world.get::<BackgroundColor>(card).unwrap().0, back);
+        assert_ne!(back, assets.card[0].0.color);
     }
 }
@@ -28,6 +28,8 @@ impl<T: Clone> Lens<T> for BeTween<T> {
         }
     }
 }
+/// User data of the completion event of the turning and shaking cards
+pub const SETTLED: u64 = 1;
 pub fn rot_seq(duration: std::time::Duration) -> Sequence<Transform> {
     let start = 0.;
     let end = std::f32::consts::PI / 2.;
@@ -38,7 +40,7 @@ pub fn rot_seq(duration: std::time::Duration) -> Sequence<Transform> {
             TransformRotateYLens { start, end },
         )
     };
-    tween(start, end).then(tween(end, start))
+    tween(start, end).then(tween(end, start).with_completed_event(SETTLED))
 }
 pub fn vis_seq(duration: std::time::Duration, show: bool) -> Tween<Visibility> {
     Tween::new(
@@ -59,8 +61,9 @@ pub fn shake_seq(duration: std::time::Duration) -> Sequence<Transform> {
     };
     let pi = std::f32::consts::PI;
     Sequence::new((1..4).rev().map(|i| {
+        let settle = tween(-pi / 12. / i as f32, 0., i);
         tween(0., pi / 12. / i as f32, i)
             .then(tween(-pi / 12. / i as f32, -pi / 12. / i as f32, i))
-            .then(tween(-pi / 12. / i as f32, 0., i))
+            .then(if i == 1 { settle.with_completed_event(SETTLED) } else { settle })
     }))
 }
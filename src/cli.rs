@@ -0,0 +1,117 @@
+//! Command line options, to launch straight into a configuration
+use {
+    memory::{MemoryGOpts, RuleSet, MAX_SEATS},
+    std::str::FromStr,
+};
+
+/// Options of any game
+pub const GAME: &str = "Game:
+    --level <0-5>            Level, which sets the size of the board
+    --rule <RULE>            AnyColor, SameColor, Zebra, TwoDecks or CheckeredDeck
+    --humans <N>             Humans taking turns
+    --bots <N>               Bots to play against
+    --seed <N>               Seed of the first deal
+    --combo, --no-combo      Another turn after finding a pair
+    --duel, --no-duel        One flip per turn, alternating the players
+    --race, --no-race        Everyone on their own board
+    --full-plate, --no-full-plate
+                             Any card can pair with any other";
+/// Arguments passed to the binary
+pub struct Cli {
+    args: Vec<String>,
+    usage: String,
+}
+impl Cli {
+    /// Arguments of the binary, with the sections of options it takes
+    pub fn parse(name: &str, sections: &[&str]) -> Self {
+        let cli = Self {
+            args: std::env::args().skip(1).collect(),
+            usage: format!(
+                "Usage: {name} [OPTIONS]\n\n{}\n\n    --help                   Print this help",
+                sections.join("\n")
+            ),
+        };
+        if cli.flag("--help") {
+            println!("{}", cli.usage);
+            std::process::exit(0);
+        }
+        cli.check();
+        cli
+    }
+    /// Fail on any argument not in the usage, or a value missing after a flag taking one
+    fn check(&self) {
+        let words = self.usage.split_whitespace().collect::<Vec<_>>();
+        let mut args = self.args.iter();
+        while let Some(arg) = args.next() {
+            let flag = words
+                .iter()
+                .position(|w| arg.starts_with("--") && w.trim_end_matches(',') == arg);
+            let Some(i) = flag else { self.fail(&format!("Unknown option {arg}")) };
+            let takes_value = words.get(i + 1).map_or(false, |w| w.starts_with('<'));
+            if takes_value && args.next().is_none() {
+                self.fail(&format!("Missing the value of {arg}"));
+            }
+        }
+    }
+    pub fn fail(&self, msg: &str) -> ! {
+        eprintln!("{msg}\n\n{}", self.usage);
+        std::process::exit(2)
+    }
+    pub fn flag(&self, flag: &str) -> bool {
+        self.args.iter().any(|arg| arg == flag)
+    }
+    pub fn value(&self, flag: &str) -> Option<&str> {
+        let i = self.args.iter().position(|arg| arg == flag)?;
+        match self.args.get(i + 1) {
+            Some(value) => Some(value.as_str()),
+            None => self.fail(&format!("Missing the value of {flag}")),
+        }
+    }
+    pub fn parse_value<T: FromStr>(&self, flag: &str) -> Option<T> {
+        self.value(flag).map(|value| {
+            value
+                .parse()
+                .unwrap_or_else(|_| self.fail(&format!("Invalid value for {flag}: {value}")))
+        })
+    }
+    /// `--name` to turn on, `--no-name` to turn off
+    fn toggle(&self, name: &str) -> Option<bool> {
+        if self.flag(&format!("--no-{name}")) {
+            Some(false)
+        } else if self.flag(&format!("--{name}")) {
+            Some(true)
+        } else {
+            None
+        }
+    }
+    /// Override the restored options with the ones given
+    pub fn apply(&self, opts: &mut MemoryGOpts) {
+        if let Some(level) = self.parse_value::<u8>("--level") {
+            if level > 5 {
+                self.fail("Level should be from 0 to 5");
+            }
+            opts.level = level;
+        }
+        if let Some(rule) = self.value("--rule") {
+            opts.mode.rule = RuleSet::from_str(rule).unwrap_or_else(|e| self.fail(&e));
+        }
+        if let Some(humans) = self.parse_value::<u8>("--humans") {
+            if humans == 0 {
+                self.fail("At least one human is needed");
+            }
+            opts.players.0 = humans;
+        }
+        if let Some(bots) = self.parse_value::<u8>("--bots") {
+            opts.players.1 = bots;
+            opts.human_first |= bots == 0;
+        }
+        if opts.players.0.saturating_add(opts.players.1) > MAX_SEATS {
+            self.fail(&format!("At most {MAX_SEATS} seats, humans and bots combined"));
+        }
+        opts.seed = self.parse_value("--seed").or(opts.seed);
+        opts.mode.combo = self.toggle("combo").unwrap_or(opts.mode.combo);
+        opts.mode.duel = self.toggle("duel").unwrap_or(opts.mode.duel);
+        opts.mode.full_plate = self.toggle("full-plate").unwrap_or(opts.mode.full_plate);
+        opts.mode.race = self.toggle("race").unwrap_or(opts.mode.race);
+    }
+}
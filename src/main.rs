@@ -1,8 +1,29 @@
-use {bevy::prelude::*, memory::*, menu_plugin::MenuMaterials, std::time::Duration};
+use {
+    bevy::{prelude::*, window::WindowMode},
+    cli::Cli,
+    memory::*,
+    menu_plugin::MenuMaterials,
+    std::time::Duration,
+};
+
+mod cli;
 
 #[cfg(feature = "dev")]
 use bevy_inspector_egui::WorldInspectorPlugin;
 
+/// Options of the graphical game only
+const APP: &str = "    --start                  Start the game right away, skipping the menu
+
+Window:
+    --width <PX>             Width of the window
+    --height <PX>            Height of the window
+    --fullscreen             Borderless fullscreen
+
+Network:
+    --host <ADDR>            Host a game
+    --join <ADDR>            Join a hosted game
+    --spectate <ADDR>        Stream the game to spectators";
+
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
 enum Game {
     Memory,
@@ -12,14 +33,10 @@ enum Game {
 /// Timer to help start another game after completing one
 #[bevy_main]
 pub fn main() {
+    let cli = Cli::parse("recall-stones", &[cli::GAME, APP]);
     let mut app = App::new();
     app.add_plugins(DefaultPlugins.set(WindowPlugin {
-        window: WindowDescriptor {
-            title: "Recall Stones! - A Concentration Game".to_string(),
-            width: 480.,
-            height: 720.,
-            ..Default::default()
-        },
+        window: window(&cli),
         ..default()
     }))
     .init_resource::<MenuMaterials>();
@@ -27,10 +44,21 @@ pub fn main() {
     #[cfg(target_arch = "wasm32")]
     app.add_system(handle_browser_resize);
 
+    network(&mut app, &cli);
     app.add_plugin(MemoryGamePlugin(Game::Memory))
         .add_state(Game::Menu)
         .add_system(game_timer)
         .add_startup_system(startup);
+    let saved = app.world.resource::<MemoryGOpts>().clone();
+    cli.apply(&mut app.world.resource_mut::<MemoryGOpts>());
+    // Clients wait for the deals of the host
+    if app.world.contains_resource::<net::NetClient>() {
+        app.world.resource_mut::<MemoryGOpts>().auto_start = false;
+    } else if cli.flag("--start") {
+        app.insert_resource(SkipMenu);
+    }
+    let given = app.world.resource::<MemoryGOpts>().clone();
+    app.insert_resource(Overrides { saved, given });
 
     // Debug hierarchy inspector
     #[cfg(feature = "dev")]
@@ -38,6 +66,28 @@ pub fn main() {
 
     app.run();
 }
+/// Window sized, or fullscreen, from the command line
+fn window(cli: &Cli) -> WindowDescriptor {
+    WindowDescriptor {
+        title: "Recall Stones! - A Concentration Game".to_string(),
+        width: size(cli, "--width").unwrap_or(480.),
+        height: size(cli, "--height").unwrap_or(720.),
+        mode: if cli.flag("--fullscreen") {
+            WindowMode::BorderlessFullscreen
+        } else {
+            WindowMode::Windowed
+        },
+        ..default()
+    }
+}
+/// Size of the window, in pixels, from 1 to the largest screens
+fn size(cli: &Cli, flag: &str) -> Option<f32> {
+    let px = cli.parse_value::<f32>(flag)?;
+    if !(1.0..=16384.).contains(&px) {
+        cli.fail(&format!("{flag} should be from 1 to 16384 pixels"));
+    }
+    Some(px)
+}
 /// Pre launch setup of assets and options
 fn startup(mut commands: Commands, mut menu: ResMut<MenuMaterials>, mut windows: ResMut<Windows>) {
     commands.spawn(Camera2dBundle::default());
@@ -49,6 +99,21 @@ fn startup(mut commands: Commands, mut menu: ResMut<MenuMaterials>, mut windows:
     // menu.size = window.physical_width().min(window.physical_height()) as f32;
 }
 
+/// Host a game with `--host <addr>`, or join one with `--join <addr>`.
+/// Spectators can watch on `--spectate <addr>`
+fn network(app: &mut App, cli: &Cli) {
+    if let Some(addr) = cli.value("--spectate") {
+        app.insert_resource(
+            spectate::Spectators::bind(addr).expect("Could not stream to spectators"),
+        );
+    }
+    if let Some(addr) = cli.value("--host") {
+        app.insert_resource(net::NetHost::bind(addr).expect("Could not host the game"));
+    } else if let Some(addr) = cli.value("--join") {
+        app.insert_resource(net::NetClient::connect(addr).expect("Could not join the game"));
+    }
+}
+
 fn game_timer(mut state: ResMut<State<Game>>, time: Res<Time>, mut timer: Local<Timer>) {
     if timer.duration() == Duration::ZERO {
         timer.set_duration(Duration::from_millis(27));
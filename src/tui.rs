@@ -0,0 +1,189 @@
+//! Recall Stones in a terminal, to play over ssh or quickly try out the rules.
+//!
+//! Cards are laid out as on the boards of the game, the two halves growing out from the middle,
+//! and picked by their coordinates, like `b3`, while the bots take their turns on their own.
+use {
+    bevy::{
+        ecs::{event::Event, system::SystemState},
+        prelude::*,
+    },
+    cli::Cli,
+    memory::{
+        components::Grid,
+        events::{self, CardFlipped, GameFinished, Mismatch, Moves, PairMatched, TurnChanged},
+        Deck, MemoryGOpts, Profiles, Store,
+    },
+    std::{
+        io::{self, BufRead, Write},
+        thread::sleep,
+        time::{Duration, Instant},
+    },
+};
+
+#[path = "cli.rs"]
+mod cli;
+
+const RESET: &str = "\x1b[0m";
+const RED: &str = "\x1b[31m";
+const DIM: &str = "\x1b[2m";
+const BOLD: &str = "\x1b[1m";
+
+/// Rank and suit of the card, red suits in red
+fn face(card: u8) -> String {
+    let rank = "A23456789TJQKC".chars().nth(card as usize % 14).unwrap();
+    let suit = ['♠', '♥', '♣', '♦'][card as usize % 56 / 14];
+    let color = if card / 14 % 2 == 0 { "" } else { RED };
+    format!("{color}{rank}{suit}{RESET}")
+}
+fn coord(idx: usize, grid: &Grid) -> String {
+    let (x, y) = grid.pos(idx);
+    format!("{}{}", (b'a' + x as u8) as char, y + 1)
+}
+/// Index of the card at coordinates like `b3`
+fn parse(input: &str, grid: &Grid) -> Option<usize> {
+    let mut chars = input.chars();
+    let col = chars.next()?.to_ascii_lowercase();
+    let row: usize = chars.as_str().parse().ok()?;
+    let col = (col as usize).checked_sub('a' as usize)?;
+    grid.at(col, row.checked_sub(1)?)
+}
+/// Cards face down, the flipped ones in brackets and the found pairs dimmed
+fn cell(deck: &Deck, idx: usize) -> String {
+    let card = deck.get_card(idx);
+    if deck.opened.contains(&idx) {
+        format!("[{BOLD}{}]", face(card))
+    } else if deck.is_revealed(idx) {
+        format!(" {DIM}{} ", face(card))
+    } else if card >= 56 {
+        " %% ".to_string()
+    } else {
+        " ## ".to_string()
+    }
+}
+/// Rows of the grid, the halves apart unless any card can pair with any other
+fn render(deck: &Deck, grid: &Grid, names: &[String]) -> String {
+    let mut out = String::from("   ");
+    for col in 0..grid.width {
+        out += &format!("  {} ", (b'a' + col as u8) as char);
+    }
+    for y in 0..2 * grid.rows() {
+        if y == grid.rows() && !deck.mode().full_plate {
+            out += "\n";
+        }
+        out += &format!("\n{:>2} ", y + 1);
+        for idx in (0..grid.width).map_while(|x| grid.at(x, y)) {
+            out += &cell(deck, idx);
+        }
+    }
+    out += "\n";
+    for (pl, name) in deck.seats().iter().zip(names) {
+        let current = if pl.id() == deck.player() { ">" } else { " " };
+        out += &format!(
+            "\n{current} {name}: Score {}, Opened {}, Turns {}",
+            deck.scores[pl.id() as usize],
+            deck.opened_by(pl.id()),
+            pl.deref().1
+        );
+    }
+    if let Some(left) = deck.budget_left() {
+        out += &format!("\n  Budget left: {left}");
+    }
+    out
+}
+
+/// Events sent by the moves since the last drain
+fn drain<E: Event>(world: &mut World) -> Vec<E> {
+    world.resource_mut::<Events<E>>().drain().collect()
+}
+
+fn main() {
+    let cli = Cli::parse("recall-stones-tui", &[cli::GAME]);
+    let store = Store::default();
+    let mut opts: MemoryGOpts = store.load("options.ron").unwrap_or_default();
+    cli.apply(&mut opts);
+    if opts.mode.race {
+        cli.fail("Races need a board for each player, play them in the game or pass --no-race");
+    }
+    let players = opts.create_players(0);
+    let seats = opts.seats(&players, &Profiles::load(&store).unwrap_or(Profiles(vec![])));
+    let names: Vec<String> = players
+        .iter()
+        .map(|pl| seats.name(pl.id(), pl.is_bot()))
+        .collect();
+    let mut deck = match opts.seed {
+        Some(seed) => Deck::seeded(opts.deck_params(), opts.mode, &players, seed),
+        None => Deck::init(opts.deck_params(), opts.mode, &players),
+    };
+
+    let grid = Grid::new(opts.deck_params().0 as usize);
+    // Moves are played as on a board of the game, for the same events
+    let mut app = App::new();
+    events::add_events(&mut app);
+    let board = app.world.spawn_empty().id();
+    let mut state = SystemState::<Moves>::new(&mut app.world);
+
+    let mut lines = io::stdin().lock().lines();
+    let mut log = String::new();
+    let mut clock = Instant::now();
+    let mut outcome = None;
+    while outcome.is_none() {
+        print!(
+            "\x1b[2J\x1b[H{}\n\n{}\n\n{log}\n",
+            opts.mode.desc(),
+            render(&deck, &grid, &names)
+        );
+        let player = deck.player() as usize;
+        let idx = if deck.seats()[player].is_bot() {
+            io::stdout().flush().ok();
+            sleep(Duration::from_millis(810));
+            deck.bot_move(opts.bot_tier, &mut rand::thread_rng())
+        } else {
+            print!("{}, pick a card (q to quit)> ", names[player]);
+            io::stdout().flush().ok();
+            let Some(Ok(line)) = lines.next() else { return };
+            let line = line.trim();
+            if line == "q" || line == "quit" {
+                return;
+            }
+            match parse(line, &grid) {
+                Some(idx) if deck.is_available_move(idx) => Some(idx),
+                Some(_) => {
+                    log = format!("{line} is not available");
+                    continue;
+                }
+                None => {
+                    log = "Pick a card by its column and row, like b3".to_string();
+                    continue;
+                }
+            }
+        };
+        let mut moves = state.get_mut(&mut app.world);
+        moves.tick(board, &mut deck, clock.elapsed());
+        clock = Instant::now();
+        if let Some(idx) = idx.filter(|_| !deck.completed()) {
+            moves.play(board, &mut deck, idx);
+        }
+        let world = &mut app.world;
+        log.clear();
+        for CardFlipped { idx, card, player, .. } in drain(world) {
+            let name = &names[player as usize];
+            log += &format!("{name} flipped {}: {}", coord(idx, &grid), face(card));
+        }
+        if !drain::<PairMatched>(world).is_empty() {
+            log += " - Pair!";
+        }
+        if !drain::<Mismatch>(world).is_empty() {
+            log += " - No match";
+        }
+        for TurnChanged { to, .. } in drain(world) {
+            log += &format!("\n{}'s turn", names[to as usize]);
+        }
+        outcome = drain::<GameFinished>(world).pop().map(|finished| finished.outcome);
+    }
+    println!("\x1b[2J\x1b[H{}\n\n{log}\n", render(&deck, &grid, &names));
+    opts.outcome = outcome.map(|outcome| outcome.with_names(&seats));
+    println!("{}", opts.outcome());
+    for standing in opts.outcome.iter().flat_map(|outcome| outcome.iter()) {
+        println!("{standing}");
+    }
+}